@@ -17,11 +17,21 @@ impl JsonCache {
 }
 
 impl CacheService for JsonCache {
-    fn read(self, _name: String) -> Result<Cache, Error> {
-        Err(failure::err_msg("JsonCache::read not implemented"))
+    fn read(&self, name: String) -> Result<Cache, Error> {
+        let cache_file_path = format!("{}/{}.json", &self.cache_dir, name);
+
+        let content = match fs::read_to_string(&cache_file_path) {
+            Ok(content) => content,
+            Err(e) => return Err(failure::err_msg(format!("error reading cache file '{}'\n{}", &cache_file_path, e))),
+        };
+
+        match serde_json::from_str(&content) {
+            Ok(cache) => Ok(cache),
+            Err(e) => Err(failure::err_msg(format!("error parsing cache file '{}'\n{}", &cache_file_path, e))),
+        }
     }
 
-    fn write(self, cache: Cache) -> Result<(), failure::Error> {
+    fn write(&self, cache: Cache) -> Result<(), failure::Error> {
 
         // 0. Check to see if ./cache exists
         match fs::metadata(self.cache_dir.clone()) {
@@ -50,4 +60,27 @@ impl CacheService for JsonCache {
             Err(e) => Err(failure::err_msg(format!("error creating cache file '{}'\n{}", &cache_file_path, e))),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnDef, Dialect};
+    use crate::cache::{CacheType, DataDefinition};
+
+    #[test]
+    fn read_returns_the_written_cache() {
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let cache_svc = JsonCache::new(tmp_dir.path().to_string_lossy().to_string());
+
+        let mut cache = Cache::new(String::from("testing"), CacheType::Db);
+        let dialect = Dialect { delimiter: b'\t', ..Dialect::default() };
+        cache.add_data_definition(DataDefinition::new(String::from("People"), String::from("people.tsv"), Vec::<ColumnDef>::new(), dialect.clone(), false));
+        cache_svc.write(cache).unwrap();
+
+        let cache = cache_svc.read(String::from("testing")).unwrap();
+        assert_eq!(cache.dialect_for("people.tsv"), Some((dialect, false)));
+        assert_eq!(cache.dialect_for("orders.csv"), None);
+        assert!(cache_svc.read(String::from("missing")).is_err());
+    }
+}
@@ -5,7 +5,7 @@
 use serde_derive::{Deserialize, Serialize};
 use serde_json;
 
-use crate::ColumnDef;
+use crate::{ColumnDef, Dialect};
 
 pub mod json;
 
@@ -33,24 +33,37 @@ impl Cache {
     pub fn add_data_definition(&mut self, data_def: DataDefinition) {
         self.data_definitions.push(data_def);
     }
+
+    /// returns the dialect and header setting saved for the input with the given name
+    pub fn dialect_for(&self, source: &str) -> Option<(Dialect, bool)> {
+        self.data_definitions.iter()
+            .find(|data_def| data_def.source == source)
+            .map(|data_def| (data_def.dialect.clone(), data_def.has_headers))
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DataDefinition {
     object_name: String,
-    columns: Vec<ColumnDef>
+    source: String,
+    columns: Vec<ColumnDef>,
+    dialect: Dialect,
+    has_headers: bool,
 }
 
 impl DataDefinition {
-    pub fn new(object_name: String, columns: Vec<ColumnDef>) -> Self {
+    pub fn new(object_name: String, source: String, columns: Vec<ColumnDef>, dialect: Dialect, has_headers: bool) -> Self {
         Self {
             object_name,
+            source,
             columns,
+            dialect,
+            has_headers,
         }
     }
 }
 // A trait for interacting with a cache
 pub trait CacheService {
-    fn read(self, name: String) -> Result<Cache, failure::Error>;
-    fn write(self, cache: Cache) -> Result<(), failure::Error>;
+    fn read(&self, name: String) -> Result<Cache, failure::Error>;
+    fn write(&self, cache: Cache) -> Result<(), failure::Error>;
 }
\ No newline at end of file
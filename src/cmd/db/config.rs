@@ -1,7 +1,14 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use encoding_rs::Encoding;
 use glob::{glob_with, MatchOptions};
-use crate::InputSource;
+use crate::{Dialect, InputSource, Origin, RaggedRows, TransactionMode};
+use crate::parsers::{archive, compression, stdin};
+use crate::parsers::headers::{HeaderCase, HeaderFormat};
+use crate::parsers::rows::RowFilter;
+use crate::parsers::values::ValueParser;
 use crate::{
     ConfigService,
     cmd::db::Types
@@ -10,50 +17,218 @@ use crate::{
 /// Config contains all the parameters provided by the user
 #[derive(Debug)]
 pub struct Config {
+    auto_primary_key: bool,
+    batch_size: usize,
+    bulk_load: bool,
     connection_info: String,
     db_type: Types,
     delete_data: bool,
+    dialect: Dialect,
     directories: Vec<String>,
     drop_store: bool,
+    encoding: Option<&'static Encoding>,
     extension: String,
     files: Vec<String>,
+    fixed_width_ids: bool,
+    foreign_keys: bool,
+    header_case: HeaderCase,
+    header_files: Vec<String>,
+    max_errors: Option<usize>,
     name: String,
+    no_header_files: Vec<String>,
     no_headers: bool,
+    nullable: bool,
+    numeric_columns: Vec<String>,
     one_table: Option<String>,
+    primary_key: Vec<String>,
+    ragged_rows: RaggedRows,
+    row_filter: RowFilter,
     save_cache: bool,
+    sniff_dialect: bool,
+    transaction_mode: TransactionMode,
+    value_parser: ValueParser,
+}
+
+/// DbOpts holds the command line arguments of the db command that Config is created from,
+/// naming each argument so arguments of the same type can't be passed in the wrong order
+#[derive(Debug)]
+pub struct DbOpts {
+    pub auto_primary_key: bool,
+    pub batch_size: usize,
+    pub bulk_load: bool,
+    pub connection_info: String,
+    pub db_type: Types,
+    pub delete_data: bool,
+    pub dialect: Dialect,
+    pub directories: Vec<PathBuf>,
+    pub drop_tables: bool,
+    pub encoding: Option<&'static Encoding>,
+    pub extension: String,
+    pub files: Vec<PathBuf>,
+    pub fixed_width_ids: bool,
+    pub foreign_keys: bool,
+    pub header_case: HeaderCase,
+    pub header_files: Vec<String>,
+    pub max_errors: Option<usize>,
+    pub name: String,
+    pub no_header_files: Vec<String>,
+    pub no_headers: bool,
+    pub nullable: bool,
+    pub numeric_columns: Vec<String>,
+    pub one_table: Option<String>,
+    pub primary_key: Vec<String>,
+    pub ragged_rows: RaggedRows,
+    pub row_filter: RowFilter,
+    pub save_cache: bool,
+    pub sniff_dialect: bool,
+    pub transaction_mode: TransactionMode,
+    pub value_parser: ValueParser,
 }
 
 impl Config {
     /// Creates a struct of all the CmdLine Arguments
-    pub fn new(extension: String, files_path: Vec<PathBuf>, directories: Vec<PathBuf>, db_type: Types,
-               connection_info: String, name: String, drop_tables: bool, no_headers: bool,
-               one_table: Option<String>, save_cache: bool, delete_data: bool) -> Config {
+    pub fn new(opts: DbOpts) -> Config {
         Config {
-            connection_info,
-            db_type,
-            delete_data,
-            directories: Config::convert_to_vec_of_string(directories),
-            drop_store: drop_tables,
-            extension,
-            files: Config::convert_to_vec_of_string(files_path),
-            name,
-            no_headers,
-            one_table,
-            save_cache,
+            auto_primary_key: opts.auto_primary_key,
+            batch_size: opts.batch_size,
+            bulk_load: opts.bulk_load,
+            connection_info: opts.connection_info,
+            db_type: opts.db_type,
+            delete_data: opts.delete_data,
+            dialect: opts.dialect,
+            directories: Config::convert_to_vec_of_string(opts.directories),
+            drop_store: opts.drop_tables,
+            encoding: opts.encoding,
+            extension: opts.extension,
+            files: Config::convert_to_vec_of_string(opts.files),
+            fixed_width_ids: opts.fixed_width_ids,
+            foreign_keys: opts.foreign_keys,
+            header_case: opts.header_case,
+            header_files: opts.header_files,
+            max_errors: opts.max_errors,
+            name: opts.name,
+            no_header_files: opts.no_header_files,
+            no_headers: opts.no_headers,
+            nullable: opts.nullable,
+            numeric_columns: opts.numeric_columns,
+            one_table: opts.one_table,
+            primary_key: opts.primary_key,
+            ragged_rows: opts.ragged_rows,
+            row_filter: opts.row_filter,
+            save_cache: opts.save_cache,
+            sniff_dialect: opts.sniff_dialect,
+            transaction_mode: opts.transaction_mode,
+            value_parser: opts.value_parser,
         }
     }
 
-    fn create_input_source(has_headers: bool, file_path: String) -> Result<InputSource, failure::Error> {
-        match fs::metadata(file_path.clone()) {
-            Ok(meta) => Ok(InputSource {
-                            has_headers,
-                            location: file_path,
-                            size: meta.len(),
-                        }),
-            Err(e) => Err(failure::err_msg(format!("input source error: {}", e)))
+    // creates the input sources for the file, an archive has an input source for each of
+    // the files inside of it that have one of the extensions
+    fn create_input_sources(&self, file_path: String) -> Result<Vec<InputSource>, failure::Error> {
+        if file_path == stdin::PATH {
+            return self.create_stdin_source().map(|source| vec![source]);
+        }
+
+        let meta = match fs::metadata(file_path.clone()) {
+            Ok(meta) => meta,
+            Err(e) => return Err(failure::err_msg(format!("input source error: {}", e)))
+        };
+
+        let source = InputSource {
+            cached_dialect: None,
+            detect_headers: self.should_detect_headers(&file_path),
+            dialect: self.get_dialect(),
+            encoding: self.get_encoding(),
+            find_keys: self.should_find_keys(),
+            find_references: self.should_find_foreign_keys(),
+            fixed_width_ids: self.should_find_fixed_width_ids(),
+            has_headers: self.has_headers(&file_path),
+            header_format: self.get_header_format(),
+            location: file_path.clone(),
+            numeric_columns: self.get_numeric_columns(),
+            origin: Origin::File,
+            ragged_rows: self.get_ragged_rows(),
+            row_filter: self.get_row_filter(),
+            size: meta.len(),
+            sniff_dialect: self.should_sniff_dialect(),
+            value_parser: self.get_value_parser(),
+        };
+
+        if !archive::is_archive(&file_path) {
+            return Ok(vec![source]);
+        }
+
+        match archive::list_members(&file_path, |name| self.has_extension(name) && !name.starts_with("__MACOSX/")) {
+            Ok((members, skipped)) => {
+                skipped.iter().for_each(|reason| eprintln!("input source warning: {}", reason));
+                Ok(members.into_iter()
+                    .map(|m| InputSource {
+                        detect_headers: self.should_detect_headers(&m.name),
+                        has_headers: self.has_headers(&m.name),
+                        size: m.size,
+                        origin: Origin::Archive(m),
+                        ..source.clone()
+                    })
+                    .collect())
+            },
+            Err(e) => Err(failure::err_msg(format!("input source error: {}: {}", file_path, e)))
         }
     }
 
+    // the piped content is copied to a temporary file before it's parsed
+    fn create_stdin_source(&self) -> Result<InputSource, failure::Error> {
+        let spool = match stdin::spool(&mut io::stdin()) {
+            Ok(spool) => spool,
+            Err(e) => return Err(failure::err_msg(format!("input source error: unable to read stdin: {}", e)))
+        };
+        let size = spool.as_file().metadata().map(|meta| meta.len()).unwrap_or_default();
+
+        Ok(InputSource {
+            cached_dialect: None,
+            detect_headers: self.should_detect_headers(stdin::PATH),
+            dialect: self.get_dialect(),
+            encoding: self.get_encoding(),
+            find_keys: self.should_find_keys(),
+            find_references: self.should_find_foreign_keys(),
+            fixed_width_ids: self.should_find_fixed_width_ids(),
+            has_headers: self.has_headers(stdin::PATH),
+            header_format: self.get_header_format(),
+            location: String::from(stdin::PATH),
+            numeric_columns: self.get_numeric_columns(),
+            origin: Origin::Stdin(Arc::new(spool)),
+            ragged_rows: self.get_ragged_rows(),
+            row_filter: self.get_row_filter(),
+            size,
+            sniff_dialect: self.should_sniff_dialect(),
+            value_parser: self.get_value_parser(),
+        })
+    }
+
+    // the candidate keys are only needed to check or pick a primary key and to find the keys
+    // that foreign keys refer to
+    fn should_find_keys(&self) -> bool {
+        !self.primary_key.is_empty() || self.auto_primary_key || self.should_find_foreign_keys()
+    }
+
+    // returns true if the file is one of the names, a name is the file's path or only its
+    // file name so files in different directories can be named without their directories
+    fn is_named(file_name: &str, names: &[String]) -> bool {
+        let base_name = Path::new(file_name).file_name().and_then(|name| name.to_str()).unwrap_or(file_name);
+        names.iter().any(|name| name == file_name || name == base_name)
+    }
+
+    // the extension can be a comma delimited list of extensions
+    fn get_extensions(&self) -> Vec<&str> {
+        self.extension.split(',').map(|e| e.trim()).filter(|e| !e.is_empty()).collect()
+    }
+
+    // returns true if the name ends with one of the extensions, or one of the extensions
+    // followed by the extension of a compression format
+    fn has_extension(&self, name: &str) -> bool {
+        let lower_name = compression::strip_extension(name).to_lowercase();
+        self.get_extensions().iter().any(|ext| lower_name.ends_with(&format!(".{}", ext.to_lowercase())))
+    }
+
     fn convert_to_vec_of_string(paths: Vec<PathBuf>) -> Vec<String> {
         let mut string_paths: Vec<String> = Vec::new();
 
@@ -77,44 +252,108 @@ impl ConfigService for Config {
             require_literal_separator: false,
         };
 
+        // compressed versions of the files, like data.csv.gz, and archives are matched too
+        let mut patterns: Vec<String> = Vec::new();
+        for ext in self.get_extensions() {
+            patterns.push(ext.to_string());
+            for compression_ext in compression::EXTENSIONS.iter() {
+                patterns.push(format!("{}.{}", ext, compression_ext));
+            }
+        }
+        for archive_ext in archive::EXTENSIONS.iter() {
+            patterns.push(archive_ext.to_string());
+        }
+
         // Gets the files inside the given directories and adds them to the
         // input source
         for d in &self.directories {
-            for f in  glob_with(&format!("{}/*.{}", d, &self.extension), options).unwrap() {
-                match f {
-                    Ok(file_path) => {
-                        match Config::create_input_source(self.has_headers(),file_path.into_os_string().into_string().unwrap_or_default()) {
-                            Ok(input_src) => {
-                                if !input_src.location.ends_with(".sh") {
-                                    sources.push(input_src)
-                                }
-                            },
-                            Err(e) => eprintln!("{}", e),
-                        }
-                    },
-                    Err(e) => eprintln!("ERROR: {}", e),
+            for ext in &patterns {
+                for f in  glob_with(&format!("{}/*.{}", d, ext), options).unwrap() {
+                    match f {
+                        Ok(file_path) => {
+                            match self.create_input_sources(file_path.into_os_string().into_string().unwrap_or_default()) {
+                                Ok(input_srcs) => {
+                                    for input_src in input_srcs {
+                                        if !input_src.location.ends_with(".sh") {
+                                            sources.push(input_src)
+                                        }
+                                    }
+                                },
+                                Err(e) => eprintln!("{}", e),
+                            }
+                        },
+                        Err(e) => eprintln!("ERROR: {}", e),
+                    }
                 }
             }
         }
 
-        // files
+        // files, stdin can only be read once so - is only used the first time it's given
+        let mut stdin_added = false;
         for file_path in &self.files {
-            match Config::create_input_source(self.has_headers(),file_path.clone()) {
-                Ok(input_src) => sources.push(input_src),
+            if file_path == stdin::PATH {
+                if stdin_added {
+                    eprintln!("input source error: - can only be given once");
+                    continue;
+                }
+                stdin_added = true;
+            }
+
+            match self.create_input_sources(file_path.clone()) {
+                Ok(mut input_srcs) => sources.append(&mut input_srcs),
                 Err(e) => eprintln!("{}", e),
             }
         }
 
         sources.to_owned()
     }
+    fn get_batch_size(&self) -> usize { self.batch_size }
+    fn get_dialect(&self) -> Dialect { self.dialect.clone() }
+    fn get_encoding(&self) -> Option<&'static Encoding> { self.encoding }
+    fn get_header_format(&self) -> HeaderFormat {
+        HeaderFormat {
+            case: self.header_case,
+            max_length: self.db_type.max_identifier_length(),
+        }
+    }
+    fn get_max_errors(&self) -> Option<usize> { self.max_errors }
     fn get_name(&self) -> String { self.name.clone() }
-    fn has_headers(&self) -> bool {
-        !self.no_headers
+    fn get_numeric_columns(&self) -> Vec<String> { self.numeric_columns.clone() }
+    fn get_primary_key(&self) -> Vec<String> { self.primary_key.clone() }
+    fn get_ragged_rows(&self) -> RaggedRows { self.ragged_rows }
+    fn get_row_filter(&self) -> RowFilter { self.row_filter.clone() }
+    fn get_transaction_mode(&self) -> TransactionMode { self.transaction_mode }
+    fn get_value_parser(&self) -> ValueParser { self.value_parser.clone() }
+    // the files named by --header-files and --no-header-files have the header setting they
+    // were named with, the others have a header row unless --no-headers is given
+    fn has_headers(&self, file_name: &str) -> bool {
+        if Config::is_named(file_name, &self.header_files) {
+            true
+        } else if Config::is_named(file_name, &self.no_header_files) {
+            false
+        } else {
+            !self.no_headers
+        }
     }
     fn has_single_table(&self) -> Option<String>{
         self.one_table.clone()
     }
+    fn should_auto_primary_key(&self) -> bool { self.auto_primary_key }
+    fn should_bulk_load(&self) -> bool { self.bulk_load }
     fn should_delete_data(&self) -> bool {self.delete_data }
+    // the header row of a file that was named by --header-files or --no-header-files is never
+    // detected, the other files' header rows are detected unless --no-headers is given without --sniff
+    fn should_detect_headers(&self, file_name: &str) -> bool {
+        if Config::is_named(file_name, &self.header_files) || Config::is_named(file_name, &self.no_header_files) {
+            return false;
+        }
+        !self.no_headers || self.sniff_dialect
+    }
     fn should_drop_store(&self) -> bool { self.drop_store }
+    // the references are only found between the tables of different inputs
+    fn should_find_foreign_keys(&self) -> bool { self.foreign_keys && self.one_table.is_none() }
+    fn should_find_fixed_width_ids(&self) -> bool { self.fixed_width_ids }
+    fn should_keep_nullable(&self) -> bool { self.nullable }
     fn should_save_cache(&self) -> bool { self.save_cache }
+    fn should_sniff_dialect(&self) -> bool { self.sniff_dialect }
 }
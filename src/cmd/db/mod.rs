@@ -1,14 +1,19 @@
 extern crate ansi_term;
 
 pub mod config;
+mod references;
 
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::str::FromStr;
 use ansi_term::Colour::{Green, Red, Yellow};
 use indicatif::{ProgressBar, ProgressStyle};
 
-use crate::ColumnDef;
+use crate::{ColumnDef, Dialect, InputSource, Origin, ParsedContent, RaggedRows, Records, TransactionMode};
 use crate::parsers::InputService;
+use crate::parsers::compression;
+use crate::parsers::headers::{self, HeaderCase};
+use crate::parsers::stats::ColumnStats;
 use crate::ConfigService;
 use crate::storage::StorageService;
 use crate::cache::{Cache, CacheType, DataDefinition, CacheService};
@@ -59,6 +64,10 @@ where
         let save_cache = self.config_svc.should_save_cache();
         let mut have_added_cache = false;
 
+        // the dialects saved by an earlier run are used instead of sniffing the inputs again,
+        // there is nothing to reuse when the cache hasn't been saved before
+        let saved_cache = self.cache_svc.read(self.config_svc.get_name()).ok();
+
         let using_single_table = match self.config_svc.has_single_table() {
             Some(_) => true,
             None => false,
@@ -66,16 +75,24 @@ where
         let keep_tables_delete_data = self.config_svc.should_delete_data();
         let mut need_to_create_single_table = using_single_table;
 
+        // the single table is created from the first input so the columns stay nullable
+        // in case the other inputs have empty values where the first one doesn't
+        let keep_nullable = self.config_svc.should_keep_nullable() || using_single_table;
+
         let pbar = ProgressBar::new(inputs.len() as u64);
         pbar.set_style(ProgressStyle::default_bar()
             .template("{prefix:.cyan/blue} {msg} [{bar:40.cyan/blue}] {pos:>3/blue}/{len:3}files")
             .progress_chars("=> "));
         pbar.set_prefix("Processing");
 
+        // every input is parsed before any of them are loaded so the tables can be created
+        // in an order where the tables that other tables refer to come first
+        let mut parsed: Vec<ParsedInput> = Vec::new();
         let mut num_files = 0;
-        for input in inputs {
-            pbar.set_message(&format!("{}", &input.location));
-            match self.input_svc.parse(input) {
+        for mut input in inputs {
+            pbar.set_message(&input.get_name());
+            input.cached_dialect = saved_cache.as_ref().and_then(|saved| saved.dialect_for(&input.get_name()));
+            match self.input_svc.parse(&input) {
                 Err(e) => errors.push(format!("parse error: {:?}", e)),
                 Ok(mut pc) => {
                     if !&pc.errors.is_empty() {
@@ -86,51 +103,181 @@ where
                         warnings.push(format!("the input source '{}' was not a CSV file or had no data.", pc.file_name));
                     }
 
-                    pc.set_column_data_types();
-                    pbar.set_prefix("Loading Data...");
-
-                    let table_name = self.get_table_name(&pc.file_name);
-
-                    // TODO: change this to be less hackie
-                    if !keep_tables_delete_data {
-                        if   !using_single_table || need_to_create_single_table {
-                            if let Err(e) = self.storage_svc.create_store(table_name.clone(), pc.columns.clone(), self.config_svc.should_drop_store()) {
-                                errors.push(format!("error while attempting to create '{}' table => {}", table_name, e));
-                                continue;
-                            }
-                            need_to_create_single_table = false;
-                        }
+                    if pc.ragged_rows > 0 && input.ragged_rows == RaggedRows::SideFile {
+                        warnings.push(format!("{} ragged rows of '{}' were written to '{}'", pc.ragged_rows, pc.file_name, input.bad_rows_path()));
                     }
 
-                    if keep_tables_delete_data {
-                        if let Err(e) = self.storage_svc.delete_data_in_table(table_name.clone()) {
-                            errors.push(format!("error will attempting to delete data from table '{}', error '{}'", table_name, e));
-                            continue;
-                        }
-                    }
-                    match self.store(table_name.clone(),
-                                     pc.file_name.clone(),
-                                     pc.records_parsed,
-                                     pc.columns.clone(),
-                                     pc.content.clone()) {
-                            Ok(result) => results.push(result),
-                            Err(e) => errors.push(format!("{}", e)),
+                    pc.set_column_data_types();
+                    if keep_nullable {
+                        pc.columns.iter_mut().for_each(|col| col.nullable = true);
                     }
+                    self.set_keys(&mut pc, &mut warnings);
+
+                    let table_name = self.get_table_name(&input);
+                    parsed.push(ParsedInput { input, table_name, content: pc });
+                }
+            }
+            num_files += 1;
+        }
+
+        if using_single_table {
+            merge_column_stats(&mut parsed);
+        }
+
+        if self.config_svc.should_find_foreign_keys() {
+            warnings.append(&mut references::find_references(&mut parsed));
+            parsed = references::load_order(parsed, &mut warnings);
+        }
+
+        // the tables are dropped, or their data deleted, in the reverse of the order they're
+        // loaded in so a table is emptied after the tables that refer to it
+        let table_names = table_names(&parsed);
+        let mut not_emptied: Vec<String> = Vec::new();
+        for table_name in table_names.iter().rev() {
+            if keep_tables_delete_data {
+                if let Err(e) = self.storage_svc.delete_data_in_table(table_name.clone()) {
+                    errors.push(format!("error will attempting to delete data from table '{}', error '{}'", table_name, e));
+                    not_emptied.push(table_name.clone());
+                }
+            } else if self.config_svc.should_drop_store() {
+                if let Err(e) = self.storage_svc.drop_store(table_name.clone()) {
+                    errors.push(format!("error while attempting to drop '{}' table => {}", table_name, e));
+                }
+            }
+        }
+
+        // with --transaction run every table is created and loaded in one transaction, otherwise
+        // each file's table is created and loaded in its own. The tables are dropped, or their
+        // data deleted, before the transactions start
+        let run_transaction = self.config_svc.get_transaction_mode() == TransactionMode::Run;
+        let max_errors = self.config_svc.get_max_errors();
+        let mut run_rolled_back = false;
+        // the records of the run's transaction that couldn't be stored
+        let mut run_failed = 0;
+        // the results of the tables loaded in the open transaction start here
+        let mut first_in_transaction = 0;
+        if run_transaction {
+            if let Err(e) = self.storage_svc.begin_transaction() {
+                errors.push(format!("{}", e));
+                parsed.clear();
+            }
+        }
+
+        pbar.set_prefix("Loading Data...");
+        for p in parsed {
+            pbar.set_message(&p.input.get_name());
+            let (input, table_name, pc) = (p.input, p.table_name, p.content);
+
+            if keep_tables_delete_data && not_emptied.contains(&table_name) {
+                continue;
+            }
 
+            if !run_transaction {
+                if let Err(e) = self.storage_svc.begin_transaction() {
+                    errors.push(format!("'{}' {}", table_name, e));
+                    continue;
+                }
+                first_in_transaction = results.len();
+            }
 
-                    // Todo: clean this up
-                    // I want to only save cache when I have more than one table I'm storing
-                    // data or if I am using a single table I have yet to add a data definition
-                    // to the cache
-                    if save_cache &&  ( !using_single_table || !have_added_cache) {
-                        let data_def = DataDefinition::new(table_name.clone(), pc.columns.clone());
-                        cache.add_data_definition(data_def);
-                        have_added_cache = true
+            // TODO: change this to be less hackie
+            let create_table = !keep_tables_delete_data && (!using_single_table || need_to_create_single_table);
+            if create_table {
+                if let Err(e) = self.storage_svc.create_store(table_name.clone(), pc.columns.clone(), false) {
+                    errors.push(format!("error while attempting to create '{}' table => {}", table_name, e));
+                    self.roll_back(&mut results[first_in_transaction..], &mut errors);
+                    if run_transaction {
+                        run_rolled_back = true;
+                        break;
                     }
-                    pbar.inc(1)
+                    continue;
                 }
+                need_to_create_single_table = false;
+            }
+
+            let loaded = match self.store(table_name.clone(), &input, &pc) {
+                    Ok(mut result) => {
+                        if input.sniff_dialect {
+                            result.dialect = Some((pc.dialect.clone(), pc.has_headers));
+                        }
+                        result.no_header_row = input.detect_headers && !input.sniff_dialect && !pc.has_headers;
+                        result.primary_key = pc.columns.iter().filter(|col| col.primary_key).map(|col| col.name.clone()).collect();
+                        result.ragged_rows = pc.ragged_rows;
+                        if let Some(e) = &result.bulk_load_error {
+                            errors.push(format!("'{}' bulk load error -> {} -> the records were inserted instead", table_name, e));
+                        }
+                        errors.extend(result.failed_batches.iter().map(|e| format!("'{}' {}", table_name, e)));
+                        errors.extend(result.failed_records.iter().map(|e| format!("'{}' {}", table_name, e)));
+                        warnings.extend(result.warnings.iter().map(|w| format!("'{}' {}", table_name, w)));
+                        // with the default case the names that csv-to 0.5 made differently are shown too,
+                        // tables it created have those names
+                        let keep_case = input.header_format.case == HeaderCase::Keep;
+                        result.renamed = pc.columns.iter()
+                            .enumerate()
+                            .filter_map(|(idx, col)| col.header.as_ref().map(|header| {
+                                let legacy = headers::legacy_name(idx, header);
+                                match keep_case && legacy != col.name {
+                                    true => (header.clone(), format!("{} (was {} in csv-to 0.5)", col.name, legacy)),
+                                    false => (header.clone(), col.name.clone()),
+                                }
+                            }))
+                            .collect();
+
+                        // only the records the database couldn't store are counted against --max-errors,
+                        // the rows that couldn't be parsed or were set aside are reported on their own
+                        let failed = result.num_failed;
+                        run_failed += failed;
+                        let num_failed = if run_transaction { run_failed } else { failed };
+                        results.push(result);
+                        match max_errors {
+                            Some(max) if num_failed > max => {
+                                errors.push(format!("'{}' {} records couldn't be stored, more than the {} allowed by --max-errors", table_name, num_failed, max));
+                                false
+                            },
+                            _ => true,
+                        }
+                    },
+                    Err(e) => {
+                        errors.push(format!("{}", e));
+                        false
+                    },
+            };
+
+            if !loaded {
+                // a rolled back single table is created again with the next file
+                need_to_create_single_table = need_to_create_single_table || (using_single_table && create_table);
+                self.roll_back(&mut results[first_in_transaction..], &mut errors);
+                if run_transaction {
+                    run_rolled_back = true;
+                    break;
+                }
+            } else if !run_transaction {
+                if let Err(e) = self.storage_svc.commit_transaction() {
+                    errors.push(format!("'{}' {}", table_name, e));
+                    results[first_in_transaction..].iter_mut().for_each(DBResults::mark_rolled_back);
+                }
+            }
+
+
+            // Todo: clean this up
+            // I want to only save cache when I have more than one table I'm storing
+            // data or if I am using a single table I have yet to add a data definition
+            // to the cache
+            if save_cache &&  ( !using_single_table || !have_added_cache) {
+                let data_def = DataDefinition::new(table_name.clone(), input.get_name(), pc.columns.clone(), pc.dialect.clone(), pc.has_headers);
+                cache.add_data_definition(data_def);
+                have_added_cache = true
+            }
+            pbar.inc(1)
+        }
+
+        if run_rolled_back {
+            errors.push(String::from("the run's transaction was rolled back, none of the tables were loaded"));
+        } else if run_transaction {
+            if let Err(e) = self.storage_svc.commit_transaction() {
+                errors.push(format!("{}", e));
+                results.iter_mut().for_each(DBResults::mark_rolled_back);
             }
-            num_files += 1;
         }
         pbar.finish_and_clear();
 
@@ -188,23 +335,160 @@ where
         }
     }
 
-    fn store(&self, name: String, file_name: String, records_parsed: usize, columns: Vec<ColumnDef>, content: Vec<csv::StringRecord>) -> Result<DBResults, failure::Error> {
-        let insert_stmt = self.storage_svc.create_insert_stmt(name.clone(), columns.clone());
+    // rolls back the open transaction, the results are the results of the tables loaded in it
+    fn roll_back(&self, results: &mut [DBResults], errors: &mut Vec<String>) {
+        if let Err(e) = self.storage_svc.rollback_transaction() {
+            errors.push(format!("{}", e));
+        }
+        results.iter_mut().for_each(DBResults::mark_rolled_back);
+    }
+
+    // marks the columns of the primary key the user gave, or with --auto-primary-key the first
+    // candidate key. When the key is picked automatically the other single column candidate
+    // keys are made UNIQUE
+    fn set_keys(&self, pc: &mut ParsedContent, warnings: &mut Vec<String>) {
+        let given_key = self.config_svc.get_primary_key();
+        let auto_key = given_key.is_empty() && self.config_svc.should_auto_primary_key();
+        if auto_key && pc.keys_unchecked {
+            warnings.push(format!("'{}' has no primary key, its keys were not checked: too many rows", pc.file_name));
+            return;
+        }
+        let key = match auto_key {
+            true => pc.candidate_keys.first().cloned().unwrap_or_default(),
+            false => given_key,
+        };
+
+        let missing: Vec<&String> = key.iter()
+            .filter(|name| !pc.columns.iter().any(|col| col.has_name(name)))
+            .collect();
+        if !missing.is_empty() {
+            warnings.push(format!("'{}' has no primary key, it doesn't have the column(s) {:?}", pc.file_name, missing));
+            return;
+        }
+
+        // the key can be given with the headers or the column names, the candidate keys have
+        // the column names
+        let key: Vec<String> = key.iter()
+            .filter_map(|name| pc.columns.iter().find(|col| col.has_name(name)))
+            .map(|col| col.name.clone())
+            .collect();
+        let is_candidate = pc.candidate_keys.iter()
+            .any(|candidate| candidate.len() == key.len() && candidate.iter().all(|name| key.contains(name)));
+        if !key.is_empty() && pc.keys_unchecked {
+            warnings.push(format!("the primary key {:?} of '{}' was not checked: too many rows", key, pc.file_name));
+        } else if !key.is_empty() && !is_candidate {
+            warnings.push(format!("the primary key {:?} of '{}' has repeated or empty values, the records that repeat a key won't be loaded", key, pc.file_name));
+        }
+
+        for col in pc.columns.iter_mut() {
+            col.primary_key = key.contains(&col.name);
+        }
 
-        match self.storage_svc.store_data( columns.clone(), content, insert_stmt) {
-            Ok(records_inserted) => Ok(DBResults::new(name.clone(), file_name.to_string(), records_parsed, records_inserted)),
-            Err(e) => Err(e)
+        if auto_key {
+            for candidate in pc.candidate_keys.iter().skip(1).filter(|candidate| candidate.len() == 1) {
+                if let Some(col) = pc.columns.iter_mut().find(|col| col.name == candidate[0]) {
+                    col.unique = !col.primary_key;
+                }
+            }
         }
     }
 
-    fn get_table_name(&self, file_path: &str) -> String {
+    // streams the input's records into the store, the input is re-read here rather than
+    // holding on to the records read while its column data types were determined. Nothing is
+    // stored when a bulk load fails so the input is read again and its records are inserted
+    // to find the records that couldn't be loaded
+    fn store(&self, name: String, input: &InputSource, pc: &ParsedContent) -> Result<DBResults, failure::Error> {
+        let columns: Vec<ColumnDef> = pc.columns.clone();
+        let content: Records = self.input_svc.records(input, &pc.dialect, pc.has_headers)?;
+
+        let batch_size = self.config_svc.get_batch_size();
+        let commit_every = match self.config_svc.get_transaction_mode() {
+            TransactionMode::Rows(every) => Some(every),
+            TransactionMode::File | TransactionMode::Run => None,
+        };
+
+        let mut bulk_load_error = None;
+        let stored = match self.config_svc.should_bulk_load() {
+            true => self.storage_svc.bulk_load_data(name.clone(), columns.clone(), content, &input.value_parser)
+                .or_else(|e| {
+                    bulk_load_error = Some(format!("{}", e));
+                    let content: Records = self.input_svc.records(input, &pc.dialect, pc.has_headers)?;
+                    self.storage_svc.store_data(name.clone(), columns.clone(), content, batch_size, commit_every, &input.value_parser)
+                })?,
+            false => self.storage_svc.store_data(name.clone(), columns.clone(), content, batch_size, commit_every, &input.value_parser)?,
+        };
+
+        // the records that never reached the store either couldn't be parsed or were ragged rows
+        // written to the side file
+        let not_read = pc.records_parsed.saturating_sub(stored.num_stored + stored.num_failed);
+        let set_aside = match input.ragged_rows {
+            RaggedRows::SideFile => pc.ragged_rows.min(not_read),
+            _ => 0,
+        };
+
+        let mut result = DBResults::new(name.clone(), input.get_name(), pc.records_parsed, stored.num_stored);
+        result.num_failed = stored.num_failed;
+        result.parse_errors = not_read - set_aside;
+        result.set_aside = set_aside;
+        result.bulk_load_error = bulk_load_error;
+        result.num_committed = stored.num_committed;
+        result.failed_batches = stored.failed_batches;
+        result.failed_records = stored.failed_records;
+        result.warnings = stored.warnings;
+        Ok(result)
+    }
+
+    fn get_table_name(&self, input: &InputSource) -> String {
         if let Some(table_name) = &self.config_svc.has_single_table() {
             return table_name.clone();
         }
 
-        let name = String::from(Path::new(&file_path).file_name().unwrap().to_str().unwrap());
-        let first_letter = name.trim_right_matches(".csv").chars().next().unwrap();
-        name.trim_right_matches(".csv").to_string().replace(first_letter, &first_letter.to_string().to_uppercase())
+        // the extension is dropped so files like data.csv, data.tsv, and data.csv.gz all become Data.
+        // Files inside of an archive keep the directories they are in, orders/2019.csv becomes Orders_2019.
+        // Piped data has no file name so the run's name is used
+        let name = match &input.origin {
+            Origin::File => String::from(Path::new(compression::strip_extension(&input.location)).file_stem().unwrap().to_str().unwrap()),
+            Origin::Archive(member) => Path::new(compression::strip_extension(&member.name)).with_extension("")
+                .to_string_lossy()
+                .replace('/', "_")
+                .replace('\\', "_"),
+            Origin::Stdin(_) => self.config_svc.get_name(),
+        };
+        let first_letter = name.chars().next().unwrap();
+        name.replace(first_letter, &first_letter.to_string().to_uppercase())
+    }
+}
+
+// an input whose columns have been parsed, waiting to be loaded into its table
+struct ParsedInput {
+    input: InputSource,
+    table_name: String,
+    content: ParsedContent,
+}
+
+// returns the name of each input's table once, in the order the tables are first loaded. Inputs
+// with the same table name don't have to be next to each other once they're put in load order
+fn table_names(parsed: &[ParsedInput]) -> Vec<String> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    parsed.iter()
+        .filter(|p| seen.insert(&p.table_name))
+        .map(|p| p.table_name.clone())
+        .collect()
+}
+
+// the single table is created from the first input's columns, every input's columns are given
+// the stats of the columns with the same name in all of the inputs so the table's sized types
+// fit the values of the inputs that are loaded after the first
+fn merge_column_stats(parsed: &mut [ParsedInput]) {
+    let mut merged: HashMap<String, ColumnStats> = HashMap::new();
+    for col in parsed.iter().flat_map(|p| p.content.columns.iter()) {
+        merged.entry(col.name.to_lowercase()).or_default().merge(&col.stats);
+    }
+
+    for col in parsed.iter_mut().flat_map(|p| p.content.columns.iter_mut()) {
+        if let Some(stats) = merged.get(&col.name.to_lowercase()) {
+            col.stats = stats.clone();
+        }
     }
 }
 
@@ -214,6 +498,34 @@ struct DBResults {
     file_name: String,
     num_parsed: usize,
     num_stored: usize,
+    // the records the database couldn't store
+    num_failed: usize,
+    // the rows that couldn't be parsed
+    parse_errors: usize,
+    // the ragged rows that were written to the side file instead of being loaded
+    set_aside: usize,
+    // the stored records that were committed before the table's transaction ended
+    num_committed: usize,
+    // why the bulk load failed when the records had to be inserted instead
+    bulk_load_error: Option<String>,
+    // the sniffed dialect and whether or not a header row was found
+    dialect: Option<(Dialect, bool)>,
+    // why each batch of records that had to be inserted one at a time failed
+    failed_batches: Vec<String>,
+    // why each record that couldn't be inserted failed
+    failed_records: Vec<String>,
+    // the first row was checked and found to be a record instead of a header
+    no_header_row: bool,
+    // the columns of the table's primary key
+    primary_key: Vec<String>,
+    // the number of records with more or fewer fields than the header
+    ragged_rows: usize,
+    // the table's transaction was rolled back
+    rolled_back: bool,
+    // the headers that were changed to make the column names, and the names they became
+    renamed: Vec<(String, String)>,
+    // the warnings the database gave while the records were loaded
+    warnings: Vec<String>,
 }
 
 impl DBResults {
@@ -223,20 +535,87 @@ impl DBResults {
             file_name,
             num_parsed,
             num_stored,
+            num_failed: 0,
+            parse_errors: 0,
+            set_aside: 0,
+            num_committed: 0,
+            bulk_load_error: None,
+            dialect: None,
+            failed_batches: Vec::new(),
+            failed_records: Vec::new(),
+            no_header_row: false,
+            primary_key: Vec::new(),
+            ragged_rows: 0,
+            rolled_back: false,
+            renamed: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
+    // a table whose transaction was rolled back only keeps the records that were committed
+    // while it was loaded
+    fn mark_rolled_back(&mut self) {
+        self.rolled_back = true;
+        self.num_stored = self.num_committed;
+    }
+
     pub fn get_results(&self, using_single_table: bool) -> Result<String, failure::Error> {
         let mut name = &self.name;
         if using_single_table {
             name = &self.file_name;
         }
 
+        // each renamed header is listed on its own line under the table's results
+        let renamed: String = self.renamed.iter()
+            .map(|(header, name)| format!("\n    '{}' -> {}", header, name))
+            .collect();
+
+        if self.rolled_back {
+            return Err(failure::err_msg(format!("❌ {}: rolled back, {} of {} records were kept{}", name, self.num_stored, self.num_parsed, renamed)));
+        }
+
         if self.num_stored != self.num_parsed {
-           return  Err(failure::err_msg(format!("❌ {}: had {} errors", name, self.num_parsed - self.num_stored)));
+            let causes: Vec<String> = vec![(self.num_failed, "insert errors"), (self.parse_errors, "parse errors"),
+                                           (self.set_aside, "rows set aside"), (self.ragged_rows, "ragged rows")]
+                .into_iter()
+                .filter(|(n, _)| *n > 0)
+                .map(|(n, cause)| format!("{} {}", n, cause))
+                .collect();
+            let causes = match causes.is_empty() {
+                true => String::new(),
+                false => format!(" ({})", causes.join("; ")),
+            };
+           return  Err(failure::err_msg(format!("❌ {}: had {} errors{}{}", name, self.num_parsed.saturating_sub(self.num_stored), causes, renamed)));
         }
 
-        Ok(format!("✅ {}: {} records loaded", name, &self.num_stored))
+        let mut details: Vec<String> = Vec::new();
+        if let Some((dialect, has_headers)) = &self.dialect {
+            let headers = if *has_headers { "header row" } else { "no header row" };
+            details.push(format!("{}, {}", dialect, headers));
+        }
+        if self.no_header_row {
+            details.push(String::from("no header row"));
+        }
+        if !self.primary_key.is_empty() {
+            details.push(format!("primary key {}", self.primary_key.join(", ")));
+        }
+        if self.ragged_rows > 0 {
+            details.push(format!("{} ragged rows", self.ragged_rows));
+        }
+        if self.bulk_load_error.is_some() {
+            details.push(String::from("inserted after the bulk load failed"));
+        }
+        if !self.failed_batches.is_empty() {
+            details.push(format!("{} batches inserted one record at a time", self.failed_batches.len()));
+        }
+        if !self.warnings.is_empty() {
+            details.push(format!("{} load warnings", self.warnings.len()));
+        }
+
+        match details.is_empty() {
+            true => Ok(format!("✅ {}: {} records loaded{}", name, &self.num_stored, renamed)),
+            false => Ok(format!("✅ {}: {} records loaded ({}){}", name, &self.num_stored, details.join("; "), renamed)),
+        }
     }
 }
 
@@ -247,6 +626,26 @@ pub enum Types {
     SQLite,
 }
 
+impl Types {
+    /// returns the most characters a table or column name can have, None when the database
+    /// has no limit
+    pub fn max_identifier_length(&self) -> Option<usize> {
+        match self {
+            Types::MySQL => Some(64),
+            Types::Postgres => Some(63),
+            Types::SQLite => None,
+        }
+    }
+
+    /// returns true if the database has a bulk loader that is faster than inserts
+    pub fn has_bulk_loader(&self) -> bool {
+        match self {
+            Types::MySQL | Types::Postgres => true,
+            Types::SQLite => false,
+        }
+    }
+}
+
 impl FromStr for Types {
     type Err = error::DbError;
 
@@ -286,3 +685,39 @@ pub mod error {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use barrel::types::BaseType;
+    use super::*;
+    use crate::DataTypes;
+
+    fn parsed_input(file_name: &str, values: &[&str]) -> ParsedInput {
+        let mut col = ColumnDef { name: String::from("name"), data_type: DataTypes::String, ..ColumnDef::default() };
+        for val in values {
+            col.stats.add(val, DataTypes::String, &Default::default());
+        }
+        ParsedInput {
+            input: InputSource::default(),
+            table_name: String::from("People"),
+            content: ParsedContent::new(vec![col], Vec::new(), String::from(file_name), values.len()),
+        }
+    }
+
+    #[test]
+    fn table_names_lists_each_table_once() {
+        let parsed: Vec<ParsedInput> = ["Customers", "Orders", "Products", "Orders"].iter()
+            .map(|name| ParsedInput { table_name: name.to_string(), ..parsed_input("a.csv", &[]) })
+            .collect();
+        assert_eq!(table_names(&parsed), vec!["Customers", "Orders", "Products"]);
+    }
+
+    #[test]
+    fn merge_column_stats_sizes_the_single_table_to_every_input() {
+        let mut parsed = vec![parsed_input("a.csv", &["Rob", "Ann"]), parsed_input("b.csv", &["Bartholomew"])];
+        merge_column_stats(&mut parsed);
+
+        for p in &parsed {
+            assert_eq!(p.content.columns[0].to_database_type(), BaseType::Varchar(11), "{}", p.content.file_name);
+        }
+    }
+}
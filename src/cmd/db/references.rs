@@ -0,0 +1,252 @@
+//! Finds the columns of each input whose values are all in a key column of another input's
+//! table so they can be created as foreign keys, and orders the inputs so the tables that are
+//! referred to are created and loaded before the tables that refer to them
+use crate::{DataTypes, Reference};
+use super::ParsedInput;
+
+/// looks for the columns whose values are all in a single column key of another input's
+/// table and whose names look like they refer to it, e.g. customer_id or customer for the id
+/// column of Customers. Columns that refer to a primary key or UNIQUE column are given the
+/// reference, the others are returned as suggestions since a foreign key needs a key to
+/// refer to
+pub fn find_references(parsed: &mut [ParsedInput]) -> Vec<String> {
+    let mut suggestions: Vec<String> = Vec::new();
+
+    for child in 0..parsed.len() {
+        for col_idx in 0..parsed[child].content.columns.len() {
+            let (parent, key_idx) = match find_referenced_key(parsed, child, col_idx) {
+                Some(found) => found,
+                None => continue,
+            };
+
+            let parent_key: Vec<&String> = parsed[parent].content.columns.iter()
+                .filter(|col| col.primary_key)
+                .map(|col| &col.name)
+                .collect();
+            let key = &parsed[parent].content.columns[key_idx];
+            let reference = Reference {
+                table: parsed[parent].table_name.clone(),
+                column: key.name.clone(),
+            };
+
+            if key.unique || parent_key == vec![&key.name] {
+                parsed[child].content.columns[col_idx].references = Some(reference);
+            } else {
+                suggestions.push(format!("{}.{} looks like a foreign key of {}.{}, use --primary-key or --auto-primary-key to make {} a key so the foreign key is created",
+                                         parsed[child].table_name, parsed[child].content.columns[col_idx].name,
+                                         reference.table, reference.column, reference.column));
+            }
+        }
+    }
+
+    suggestions
+}
+
+/// orders the inputs so the tables other tables refer to come first, otherwise the inputs
+/// keep their order. Tables that refer to each other can't both be created with their
+/// foreign keys, the references of the first one are removed and returned as warnings
+pub fn load_order(parsed: Vec<ParsedInput>, warnings: &mut Vec<String>) -> Vec<ParsedInput> {
+    let mut remaining: Vec<Option<ParsedInput>> = parsed.into_iter().map(Some).collect();
+    let mut ordered: Vec<ParsedInput> = Vec::with_capacity(remaining.len());
+
+    while ordered.len() < remaining.len() {
+        let waiting: Vec<String> = remaining.iter()
+            .filter_map(|p| p.as_ref().map(|p| p.table_name.clone()))
+            .collect();
+        let refers_to_waiting = |p: &ParsedInput| p.content.columns.iter()
+            .filter_map(|col| col.references.as_ref())
+            .any(|reference| reference.table != p.table_name && waiting.contains(&reference.table));
+
+        let next = remaining.iter()
+            .position(|p| p.as_ref().map_or(false, |p| !refers_to_waiting(p)))
+            .unwrap_or_else(|| remaining.iter().position(|p| p.is_some()).unwrap());
+
+        let mut input = remaining[next].take().unwrap();
+        let table_name = input.table_name.clone();
+        for col in input.content.columns.iter_mut() {
+            let to_waiting = col.references.as_ref()
+                .map_or(false, |reference| reference.table != table_name && waiting.contains(&reference.table));
+            if to_waiting {
+                let reference = col.references.take().unwrap();
+                warnings.push(format!("the foreign key from {}.{} to {}.{} wasn't created, the tables refer to each other",
+                                      table_name, col.name, reference.table, reference.column));
+            }
+        }
+        ordered.push(input);
+    }
+
+    ordered
+}
+
+// returns the input and column index of the key column that the column's values are all in
+fn find_referenced_key(parsed: &[ParsedInput], child: usize, col_idx: usize) -> Option<(usize, usize)> {
+    let col = &parsed[child].content.columns[col_idx];
+    if col.data_type != DataTypes::I64 && col.data_type != DataTypes::String {
+        return None;
+    }
+
+    let values = match parsed[child].content.distinct_values.get(col_idx) {
+        Some(Some(values)) if !values.is_empty() => values,
+        _ => return None,
+    };
+
+    for (parent_idx, parent) in parsed.iter().enumerate() {
+        if parent.table_name == parsed[child].table_name {
+            continue;
+        }
+
+        for key in parent.content.candidate_keys.iter().filter(|key| key.len() == 1) {
+            let key_idx = match parent.content.columns.iter().position(|c| c.name == key[0]) {
+                Some(idx) => idx,
+                None => continue,
+            };
+
+            let key_col = &parent.content.columns[key_idx];
+            if key_col.data_type != col.data_type || !is_reference_name(&col.name, &parent.table_name, &key_col.name) {
+                continue;
+            }
+
+            if let Some(Some(keys)) = parent.content.distinct_values.get(key_idx) {
+                if values.is_subset(keys) {
+                    return Some((parent_idx, key_idx));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// a column refers to a table's key when it has the key's name, e.g. customer_id, or is named
+// after the table, with or without the key, e.g. customer or customer_id for Customers.id.
+// Names are compared without case, underscores, spaces and dashes
+fn is_reference_name(column: &str, table: &str, key: &str) -> bool {
+    let normalize = |name: &str| name.to_lowercase().replace(|c| c == '_' || c == ' ' || c == '-', "");
+    let (column, table, key) = (normalize(column), normalize(table), normalize(key));
+    let singular = match table.ends_with('s') {
+        true => &table[..table.len() - 1],
+        false => &table[..],
+    };
+
+    (column == key && key != "id")
+        || column == table
+        || column == singular
+        || column == format!("{}{}", table, key)
+        || column == format!("{}{}", singular, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::*;
+    use crate::{ColumnDef, InputSource, ParsedContent};
+
+    // creates a parsed input whose columns are all integers with the given values, the
+    // columns with all different values are candidate keys
+    fn input(table_name: &str, columns: &[(&str, &[u64])]) -> ParsedInput {
+        let mut content = ParsedContent::default();
+        for (name, values) in columns {
+            content.columns.push(ColumnDef {
+                name: name.to_string(),
+                data_type: DataTypes::I64,
+                ..ColumnDef::default()
+            });
+            let distinct: HashSet<u64> = values.iter().cloned().collect();
+            if distinct.len() == values.len() {
+                content.candidate_keys.push(vec![name.to_string()]);
+            }
+            content.distinct_values.push(Some(distinct));
+        }
+
+        ParsedInput {
+            input: InputSource {
+                find_references: true,
+                location: format!("{}.csv", table_name.to_lowercase()),
+                ..InputSource::default()
+            },
+            table_name: table_name.to_string(),
+            content,
+        }
+    }
+
+    #[test]
+    fn find_references_with_primary_key() {
+        let mut parsed = vec![
+            input("Orders", &[("id", &[1, 2, 3]), ("customer_id", &[10, 10, 11])]),
+            input("Customers", &[("id", &[10, 11, 12])]),
+        ];
+        parsed[1].content.columns[0].primary_key = true;
+
+        let suggestions = find_references(&mut parsed);
+        assert!(suggestions.is_empty());
+        assert_eq!(parsed[0].content.columns[1].references, Some(Reference { table: String::from("Customers"), column: String::from("id") }));
+        assert_eq!(parsed[0].content.columns[0].references, None);
+    }
+
+    #[test]
+    fn find_references_without_key_suggests_one() {
+        let mut parsed = vec![
+            input("Orders", &[("customer", &[10, 10, 11])]),
+            input("Customers", &[("id", &[10, 11, 12])]),
+        ];
+
+        let suggestions = find_references(&mut parsed);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(parsed[0].content.columns[0].references, None);
+    }
+
+    #[test]
+    fn find_references_skips_values_that_arent_keys_and_unrelated_names() {
+        let mut parsed = vec![
+            input("Orders", &[("customer_id", &[10, 13]), ("quantity", &[10, 11])]),
+            input("Customers", &[("id", &[10, 11, 12])]),
+        ];
+        parsed[1].content.columns[0].primary_key = true;
+
+        find_references(&mut parsed);
+        assert!(parsed[0].content.columns.iter().all(|col| col.references.is_none()));
+    }
+
+    #[test]
+    fn load_order_puts_referenced_tables_first() {
+        let mut parsed = vec![
+            input("Items", &[("order_id", &[1])]),
+            input("Orders", &[("id", &[1]), ("customer_id", &[10])]),
+            input("Customers", &[("id", &[10])]),
+        ];
+        parsed[0].content.columns[0].references = Some(Reference { table: String::from("Orders"), column: String::from("id") });
+        parsed[1].content.columns[1].references = Some(Reference { table: String::from("Customers"), column: String::from("id") });
+
+        let mut warnings = Vec::new();
+        let names: Vec<String> = load_order(parsed, &mut warnings).into_iter().map(|p| p.table_name).collect();
+        assert_eq!(names, vec!["Customers", "Orders", "Items"]);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn load_order_with_tables_that_refer_to_each_other() {
+        let mut parsed = vec![
+            input("A", &[("b_id", &[1])]),
+            input("B", &[("a_id", &[1])]),
+        ];
+        parsed[0].content.columns[0].references = Some(Reference { table: String::from("B"), column: String::from("id") });
+        parsed[1].content.columns[0].references = Some(Reference { table: String::from("A"), column: String::from("id") });
+
+        let mut warnings = Vec::new();
+        let ordered = load_order(parsed, &mut warnings);
+        assert_eq!(ordered[0].table_name, "A");
+        assert_eq!(ordered[0].content.columns[0].references, None);
+        assert!(ordered[1].content.columns[0].references.is_some());
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn is_reference_name_with_key_and_table_names() {
+        assert!(is_reference_name("customer_id", "Customers", "id"));
+        assert!(is_reference_name("Customer", "Customers", "id"));
+        assert!(is_reference_name("customer_code", "Customers", "code"));
+        assert!(is_reference_name("code", "Customers", "code"));
+        assert!(!is_reference_name("id", "Customers", "id"));
+        assert!(!is_reference_name("quantity", "Customers", "id"));
+    }
+}
@@ -1,53 +1,131 @@
 extern crate barrel;
+extern crate bzip2;
+extern crate chrono;
+extern crate chrono_tz;
+extern crate encoding_rs;
+extern crate encoding_rs_io;
 extern crate failure;
+extern crate flate2;
 extern crate glob;
 extern crate failure_derive;
 extern crate postgres;
+extern crate tar;
+extern crate tempfile;
+extern crate xz2;
+extern crate zip;
+extern crate zstd;
 
 pub mod cmd;
 pub mod cache;
 pub mod parsers;
 pub mod storage;
 
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::fmt;
 use std::fs::File;
-use std::io::{self, BufReader};
+use std::io::{self, BufRead, BufReader, Read};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 use barrel::types::BaseType;
 use csv::StringRecord;
+use encoding_rs::Encoding;
+use crate::parsers::archive::ArchiveMember;
+use crate::parsers::compression::Compression;
+use crate::parsers::headers::HeaderFormat;
+use crate::parsers::rows::RowFilter;
+use crate::parsers::stats::ColumnStats;
+use crate::parsers::values::ValueParser;
 use serde;
 use serde_derive::{Deserialize, Serialize};
 use serde_json;
+use tempfile::NamedTempFile;
 
 /// ConfigService is used to encapsulate the input from the user and allows each 'app' or sub-command
 /// in csv-to to have access to the input without having to worry about parsing and gathering
 pub trait ConfigService {
+    /// Returns the most records that are inserted with a single statement
+    fn get_batch_size(&self) -> usize;
+    /// Returns the dialect used to read the input files
+    fn get_dialect(&self) -> Dialect;
+    /// Returns the character encoding of the input files or None if it
+    /// should be detected for each file
+    fn get_encoding(&self) -> Option<&'static Encoding>;
     /// Returns a Vec<InputSource> that represents all input files/sources
     fn get_input_sources(&self) -> Vec<InputSource>;
     /// Returns the name of the run
     fn get_name(&self) -> String;
-    /// Returns true if the input files have column headers, currently
-    /// all files have them or none of them do
-    fn has_headers(&self) -> bool;
+    /// Returns the names of the columns that are numbers even when their values look
+    /// like identifiers, e.g. numbers with leading zeros
+    fn get_numeric_columns(&self) -> Vec<String>;
+    /// Returns the names of the columns that make up each table's primary key, empty when
+    /// the user didn't give one
+    fn get_primary_key(&self) -> Vec<String>;
+    /// Returns the parser used to read the dates and times in the input files
+    fn get_value_parser(&self) -> ValueParser;
+    /// Returns true if the input file has column headers, for the files whose header row is
+    /// detected it's the setting used when the file's first rows don't show it
+    fn has_headers(&self, file_name: &str) -> bool;
+    /// Returns what is done with the rows that have more or fewer fields than the header
+    fn get_ragged_rows(&self) -> RaggedRows;
+    /// Returns the most records of a transaction that can fail to be stored before the
+    /// transaction is rolled back, None when it's never rolled back for failed records
+    fn get_max_errors(&self) -> Option<usize>;
+    /// Returns how the tables and records are committed
+    fn get_transaction_mode(&self) -> TransactionMode;
+    /// Returns the leading lines, footer rows and rows matching a pattern that are dropped
+    /// from each input
+    fn get_row_filter(&self) -> RowFilter;
+    /// Returns how the headers are turned into column names
+    fn get_header_format(&self) -> HeaderFormat;
     /// Returns the name of the single table to store the data in or None if not used
     fn has_single_table(&self) -> Option<String>;
+    /// Returns true if whether the input file's first row is a header should be decided by
+    /// comparing its values with the values of the rows that follow it
+    fn should_detect_headers(&self, file_name: &str) -> bool;
+    /// Returns true if the records should be stored with the database's bulk loader instead
+    /// of inserts
+    fn should_bulk_load(&self) -> bool;
+    /// Returns true if each table's primary key should be picked from the columns whose
+    /// values are all different when no primary key is given
+    fn should_auto_primary_key(&self) -> bool;
+    /// Returns true if the columns that refer to the keys of other inputs should be found
+    /// and created as foreign keys
+    fn should_find_foreign_keys(&self) -> bool;
+    /// Returns true if the columns whose values are all the same number of digits should be
+    /// stored as strings like the other identifiers
+    fn should_find_fixed_width_ids(&self) -> bool;
     /// Returns true if the user provides --delete-data as a command line flag
     fn should_delete_data(&self) -> bool;
+    /// Returns true if every column should be nullable, otherwise columns that never
+    /// have an empty value are NOT NULL
+    fn should_keep_nullable(&self) -> bool;
     /// Returns true if tables/collections should be removed before
     /// loading the data
     fn should_drop_store(&self) -> bool;
     /// Indicates that the user asked to save to cache or not
     fn should_save_cache(&self) -> bool;
+    /// Returns true if each input's dialect and header row should be detected
+    /// from a sample of the input instead of using the given dialect
+    fn should_sniff_dialect(&self) -> bool;
 }
 
 /// Potential data types for parsed columns and will be used when creating database tables
+/// DateTime values have no timezone, DateTimeTz values end with a timezone offset
 #[derive(PartialEq,Clone, Copy, Serialize, Deserialize)]
 pub enum DataTypes {
     Empty,
+    Bool,
+    Date,
+    DateTime,
+    DateTimeTz,
     F64,
     I64,
+    Json,
     String,
+    Time,
+    Uuid,
 }
 
 impl Default for DataTypes {
@@ -59,19 +137,47 @@ impl DataTypes {
     pub fn to_str(&self) -> &str {
         match *self {
             DataTypes::Empty => "",
+            DataTypes::Bool => "bool",
+            DataTypes::Date => "date",
+            DataTypes::DateTime => "datetime",
+            DataTypes::DateTimeTz => "datetimetz",
             DataTypes::F64 => "f64",
             DataTypes::I64 => "i64",
-            DataTypes::String => "String"
+            DataTypes::Json => "json",
+            DataTypes::String => "String",
+            DataTypes::Time => "time",
+            DataTypes::Uuid => "uuid",
         }
     }
 
-    /// Converts a DataTypes value to a Barrel::BaseType
+    /// Converts a DataTypes value to a Barrel::BaseType, the stores replace the types
+    /// their database doesn't support with the closest type it does
     pub fn to_database_type(self) -> BaseType {
         match self {
             DataTypes::Empty => BaseType::Text,
+            DataTypes::Bool => BaseType::Boolean,
+            DataTypes::Date => BaseType::Custom("DATE"),
+            DataTypes::DateTime => BaseType::Custom("TIMESTAMP"),
+            DataTypes::DateTimeTz => BaseType::Custom("TIMESTAMP WITH TIME ZONE"),
             DataTypes::F64 => BaseType::Double,
             DataTypes::I64 => BaseType::Integer,
-            DataTypes::String => BaseType::Text
+            DataTypes::Json => BaseType::Custom("JSON"),
+            DataTypes::String => BaseType::Text,
+            DataTypes::Time => BaseType::Custom("TIME"),
+            DataTypes::Uuid => BaseType::Custom("UUID"),
+        }
+    }
+
+    /// Returns the data type that can hold the values of both data types. Integers widen
+    /// to floats and dates to datetimes, any other mix of types can only be held by a String
+    pub fn merge(self, other: DataTypes) -> DataTypes {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (DataTypes::Empty, t) | (t, DataTypes::Empty) => t,
+            (DataTypes::I64, DataTypes::F64) | (DataTypes::F64, DataTypes::I64) => DataTypes::F64,
+            (DataTypes::Date, DataTypes::DateTime) | (DataTypes::DateTime, DataTypes::Date) => DataTypes::DateTime,
+            (DataTypes::Date, DataTypes::DateTimeTz) | (DataTypes::DateTimeTz, DataTypes::Date) => DataTypes::DateTimeTz,
+            _ => DataTypes::String,
         }
     }
 }
@@ -79,82 +185,443 @@ impl DataTypes {
 impl fmt::Debug for DataTypes {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let printable = match *self {
-            DataTypes::Empty => "",
-            DataTypes::F64 => "f64",
-            DataTypes::I64 => "i64",
-            DataTypes::String => "string"
+            DataTypes::String => "string",
+            _ => self.to_str(),
         };
         write!(f, "{:#?}", printable)
     }
 }
 
+// the longest strings stored in a VARCHAR column, longer strings are stored as TEXT
+const MAX_VARCHAR_LENGTH: usize = 255;
+
+// the most digits a column of decimals can have and still be stored as NUMERIC. More digits
+// after the decimal point than a double can hold are usually the rounding errors of a
+// program that wrote doubles out, those columns are stored as doubles
+const MAX_NUMERIC_PRECISION: usize = 38;
+const MAX_NUMERIC_SCALE: usize = 15;
+
 /// Keeps meta data about the data in each column
+/// nullable: the column can hold NULLs, false when none of the column's values are empty
+/// header: the header the column's name was made from, None when they are the same or the
+/// input has no header row
+/// primary_key: the column is the table's primary key or part of it, a key of more than one
+/// column has the columns in the order they are in the table
+/// references: the key column of another table the column's values refer to, it is created
+/// as a FOREIGN KEY
+/// unique: the column has a UNIQUE constraint
+/// type_reason: why the column's data type isn't the one its values first suggest, e.g.
+/// numbers with leading zeros that are kept as strings
+/// stats: the range, digits and lengths of the column's values
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct ColumnDef{
     pub name: String,
     pub data_type: DataTypes,
+    pub nullable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    #[serde(default)]
+    pub primary_key: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub references: Option<Reference>,
+    #[serde(default)]
+    pub unique: bool,
     #[serde(skip)]
     pub potential_types: Vec<DataTypes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_reason: Option<String>,
+    #[serde(default)]
+    pub stats: ColumnStats,
+}
+
+/// The table and column that a foreign key refers to
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reference {
+    pub table: String,
+    pub column: String,
 }
 
 impl ColumnDef {
+    /// returns true when the name is the column's name or the header it was made from, case
+    /// is ignored so names given by the user match the names in every database
+    pub fn has_name(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name) || self.header.as_ref().map_or(false, |header| header.eq_ignore_ascii_case(name))
+    }
+
     // determines if the column's potential data type can be changed or not
     pub fn is_data_type_changeable(&self) -> bool {
         self.data_type == DataTypes::Empty || (self.data_type != DataTypes::F64 && self.data_type != DataTypes::String)
     }
+
+    /// Converts the column's data type to a Barrel::BaseType sized to the column's values.
+    /// Integers are SMALLINT, INTEGER or BIGINT depending on their range, decimals are
+    /// NUMERIC(p,s) when they can be stored exactly and short strings are VARCHAR(n)
+    pub fn to_database_type(&self) -> BaseType {
+        let stats = &self.stats;
+        match self.data_type {
+            DataTypes::I64 => {
+                match (stats.min_int, stats.max_int) {
+                    (Some(min), Some(max)) if min >= i64::from(i16::MIN) && max <= i64::from(i16::MAX) => BaseType::Custom("SMALLINT"),
+                    (Some(min), Some(max)) if min >= i64::from(i32::MIN) && max <= i64::from(i32::MAX) => BaseType::Integer,
+                    (Some(_), Some(_)) => BaseType::Custom("BIGINT"),
+                    _ => BaseType::Integer,
+                }
+            },
+            DataTypes::F64 if !stats.inexact && stats.precision() <= MAX_NUMERIC_PRECISION && stats.scale <= MAX_NUMERIC_SCALE => {
+                BaseType::Custom(static_str(format!("NUMERIC({},{})", stats.precision(), stats.scale)))
+            },
+            DataTypes::String if stats.max_length > 0 && stats.max_length <= MAX_VARCHAR_LENGTH => BaseType::Varchar(stats.max_length),
+            _ => self.data_type.to_database_type(),
+        }
+    }
+}
+
+// the sized types handed to barrel, each one is created once and shared by every column and
+// schema that uses it
+static SIZED_TYPES: Mutex<Option<HashMap<String, &'static str>>> = Mutex::new(None);
+
+// barrel's custom types are static strings, a sized type is only leaked the first time it's
+// used so the memory kept is bounded by the number of different sizes, not the calls
+fn static_str(s: String) -> &'static str {
+    let mut sized_types = SIZED_TYPES.lock().unwrap_or_else(|e| e.into_inner());
+    sized_types.get_or_insert_with(HashMap::new)
+        .entry(s)
+        .or_insert_with_key(|s| Box::leak(s.clone().into_boxed_str()))
 }
 
 impl fmt::Debug for ColumnDef {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "---\nname: {:?}\ndata_type: {:?}\n", self.name, self.data_type)
+        write!(f, "---\nname: {:?}\ndata_type: {:?}\n", self.name, self.data_type)?;
+        if let Some(reason) = &self.type_reason {
+            writeln!(f, "type_reason: {:?}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes how the fields in an input are delimited, quoted, escaped and trimmed
+/// comment: lines starting with this character are ignored
+/// delimiter: the character that separates the fields
+/// double_quote: two quote characters in a quoted field are read as a single quote
+/// escape: the character used to escape quotes in a quoted field
+/// quote: the character used to quote fields
+/// trim: which whitespace, if any, is removed from the fields
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Dialect {
+    pub comment: Option<u8>,
+    pub delimiter: u8,
+    pub double_quote: bool,
+    pub escape: Option<u8>,
+    pub quote: u8,
+    pub trim: Trim,
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect {
+            comment: None,
+            delimiter: b',',
+            double_quote: true,
+            escape: None,
+            quote: b'"',
+            trim: Trim::None,
+        }
+    }
+}
+
+impl Dialect {
+    /// parses a single character dialect option, 'tab' or '\t' can be used for a tab
+    pub fn parse_char(s: &str) -> Result<u8, failure::Error> {
+        match s {
+            "tab" | "\\t" => return Ok(b'\t'),
+            _ => (),
+        }
+
+        let bytes = s.as_bytes();
+        if bytes.len() != 1 {
+            return Err(failure::err_msg(format!("'{}' must be a single ASCII character", s)));
+        }
+
+        Ok(bytes[0])
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "delimiter {:?}, quote {:?}", self.delimiter as char, self.quote as char)?;
+        if let Some(escape) = self.escape {
+            write!(f, ", escape {:?}", escape as char)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whitespace trimming options for the fields of an input
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Trim {
+    None,
+    Headers,
+    Fields,
+    All,
+}
+
+impl Trim {
+    /// Converts the Trim value to its csv::Trim equivalent
+    pub fn to_csv_trim(self) -> csv::Trim {
+        match self {
+            Trim::None => csv::Trim::None,
+            Trim::Headers => csv::Trim::Headers,
+            Trim::Fields => csv::Trim::Fields,
+            Trim::All => csv::Trim::All,
+        }
+    }
+}
+
+impl FromStr for Trim {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Trim::None),
+            "headers" => Ok(Trim::Headers),
+            "fields" => Ok(Trim::Fields),
+            "all" => Ok(Trim::All),
+            _ => Err(failure::err_msg(format!("'{}' is not a supported trim option, valid options are none, headers, fields, and all", s)))
+        }
+    }
+}
+
+/// What is done with the rows that have more or fewer fields than the header
+/// Strict: the rows are reported as errors and aren't loaded
+/// Pad: missing fields are stored as NULL, rows with extra fields are errors
+/// Truncate: missing fields are stored as NULL and extra fields are dropped
+/// SideFile: the rows are written to a file of bad rows next to the input instead of loaded
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RaggedRows {
+    Strict,
+    Pad,
+    Truncate,
+    SideFile,
+}
+
+impl Default for RaggedRows {
+    fn default() -> RaggedRows {
+        RaggedRows::Strict
+    }
+}
+
+impl FromStr for RaggedRows {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(RaggedRows::Strict),
+            "pad" => Ok(RaggedRows::Pad),
+            "truncate" => Ok(RaggedRows::Truncate),
+            "side-file" => Ok(RaggedRows::SideFile),
+            _ => Err(failure::err_msg(format!("'{}' is not a supported ragged rows option, valid options are strict, pad, truncate, and side-file", s)))
+        }
+    }
+}
+
+/// How the tables and records are committed
+/// File: each file's table and records are committed together, or rolled back together
+/// Run: the tables and records of every file are committed together at the end of the run
+/// Rows: each file's table is committed with its records and the records are committed
+/// every n records, a rollback only undoes the records since the last commit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransactionMode {
+    File,
+    Run,
+    Rows(usize),
+}
+
+impl Default for TransactionMode {
+    fn default() -> TransactionMode {
+        TransactionMode::File
+    }
+}
+
+impl FromStr for TransactionMode {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "file" => Ok(TransactionMode::File),
+            "run" => Ok(TransactionMode::Run),
+            rows => match rows.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(TransactionMode::Rows(n)),
+                _ => Err(failure::err_msg(format!("'{}' is not a supported transaction mode, valid modes are file, run, or a number of records", s))),
+            },
+        }
     }
 }
 
 pub trait Input {
-    fn get_reader(&self) -> Result<BufReader<File>, io::Error>;
+    /// returns a reader of the input's content decompressed and transcoded to UTF-8
+    fn get_reader(&self) -> Result<Box<dyn Read>, io::Error>;
+}
+
+/// Where the content of an input source is read from
+#[derive(Clone,Debug)]
+pub enum Origin {
+    /// the file at the input's location
+    File,
+    /// a file inside of the archive at the input's location
+    Archive(ArchiveMember),
+    /// the content piped to stdin, it is copied to a temporary file because the
+    /// content is read once to determine the column data types and again to store it
+    Stdin(Arc<NamedTempFile>),
 }
 
 /// contains information about what the file contains and where it lives.
+/// cached_dialect: the dialect and header setting a saved cache has for the input, they are used
+/// instead of sniffing the input again
+/// dialect: describes how the file's fields are delimited and quoted
+/// detect_headers: whether the first row is a header is decided from the file's first rows,
+/// has_headers is used when they don't show it
+/// encoding: the character encoding of the file, detected when None
+/// fixed_width_ids: columns whose values are all the same number of digits are identifiers
+/// find_keys: the columns, and pairs of columns, that could be the file's primary key are found
+/// find_references: the distinct values of the file's columns are collected so they can be
+/// matched to the keys of other files
 /// has_headers: indicates that the file has a header row or not
-/// location: the path/uri for the input source
+/// header_format: how the file's headers are turned into column names
+/// location: the path/uri for the input source, - for stdin
+/// numeric_columns: columns whose numbers are kept as numbers even when they look like identifiers
+/// origin: where the content is read from, a file, an archive member or stdin
+/// ragged_rows: what is done with rows that have more or fewer fields than the header
+/// row_filter: the leading lines and the rows that aren't part of the file's table
 /// size: the size in bytes of the file's content
+/// sniff_dialect: the dialect and header row are detected from the file's content
+/// value_parser: reads the dates and times in the file's values
 #[derive(Clone,Debug)]
 pub struct InputSource {
+    pub cached_dialect: Option<(Dialect, bool)>,
+    pub detect_headers: bool,
+    pub dialect: Dialect,
+    pub encoding: Option<&'static Encoding>,
+    pub find_keys: bool,
+    pub find_references: bool,
+    pub fixed_width_ids: bool,
     pub has_headers: bool,
+    pub header_format: HeaderFormat,
     pub location: String,
+    pub numeric_columns: Vec<String>,
+    pub origin: Origin,
+    pub ragged_rows: RaggedRows,
+    pub row_filter: RowFilter,
     pub size: u64,
+    pub sniff_dialect: bool,
+    pub value_parser: ValueParser,
 }
 
-impl Input for InputSource {
-    fn get_reader(&self) -> Result<BufReader<File>, io::Error> {
-        match File::open(&self.location) {
-            Ok(f) => Ok(BufReader::new(f)),
-            Err(e) => Err(e),
+impl Default for InputSource {
+    fn default() -> InputSource {
+        InputSource {
+            cached_dialect: None,
+            detect_headers: false,
+            dialect: Dialect::default(),
+            encoding: None,
+            find_keys: false,
+            find_references: false,
+            fixed_width_ids: false,
+            has_headers: true,
+            header_format: HeaderFormat::default(),
+            location: String::new(),
+            numeric_columns: Vec::new(),
+            origin: Origin::File,
+            ragged_rows: RaggedRows::default(),
+            row_filter: RowFilter::default(),
+            size: 0,
+            sniff_dialect: false,
+            value_parser: ValueParser::default(),
         }
     }
 }
+
+impl InputSource {
+    /// returns the path of the file the input's ragged rows are written to, it is next to the
+    /// input with .bad.csv added to its name
+    pub fn bad_rows_path(&self) -> String {
+        match &self.origin {
+            Origin::File => format!("{}.bad.csv", self.location),
+            Origin::Archive(member) => format!("{}.{}.bad.csv", self.location, member.name.replace('/', "_").replace('\\', "_")),
+            Origin::Stdin(_) => String::from("stdin.bad.csv"),
+        }
+    }
+
+    /// returns the input's location, for archive members it is the member's path
+    /// appended to the archive's location
+    pub fn get_name(&self) -> String {
+        match &self.origin {
+            Origin::File => self.location.clone(),
+            Origin::Archive(member) => format!("{}/{}", self.location, member.name),
+            Origin::Stdin(_) => String::from("stdin"),
+        }
+    }
+}
+
+impl Input for InputSource {
+    // compressed files are detected by their leading bytes or their extension and
+    // are decompressed before their encoding is detected
+    fn get_reader(&self) -> Result<Box<dyn Read>, io::Error> {
+        let raw: Box<dyn Read> = match &self.origin {
+            Origin::File => Box::new(File::open(&self.location)?),
+            Origin::Archive(member) => member.open(&self.location)?,
+            Origin::Stdin(spool) => Box::new(spool.reopen()?),
+        };
+
+        let mut content = BufReader::new(raw);
+        let compression = Compression::detect(&self.get_name(), content.fill_buf()?);
+        let content = compression.decoder(content)?;
+
+        parsers::encoding::decode(content, self.encoding)
+    }
+}
+/// A stream of the records read from an input source, the records are read from the
+/// source as they are requested so the whole file never has to be held in memory
+pub type Records = Box<dyn Iterator<Item = StringRecord>>;
+
 /// contains information about file during and after parsing
+/// candidate_keys: the columns, or pairs of columns, whose values are all different and never
+/// empty, in the order they are proposed as the primary key
 /// columns: A Vector of th ColumnDef objects that describe the column, name, data type, etc
-/// content: Each line of the file is stored in a Vector of StringRecords (product of the CSV parsing
+/// dialect: the dialect used to read the file, given by the user or sniffed
+/// distinct_values: the hashes of each column's distinct values, only collected when the
+/// input's references are found and None for columns with too many values
 /// errors: contains all parsing errors that occurred while parsing the file
 /// the name of the file parsed
+/// has_headers: the file was read as having a header row
+/// keys_unchecked: the file had too many records for its candidate keys to be found
+/// ragged_rows: the number of records with more or fewer fields than the header
 /// the number of records parsed, used to validate that all records were stored in the database
 #[derive(Debug)]
 pub struct ParsedContent {
+    pub candidate_keys: Vec<Vec<String>>,
     pub columns: Vec<ColumnDef>,
-    pub content: Vec<StringRecord>,
+    pub dialect: Dialect,
+    pub distinct_values: Vec<Option<HashSet<u64>>>,
     pub errors: Vec<String>,
     pub file_name: String,
+    pub has_headers: bool,
+    pub keys_unchecked: bool,
+    pub ragged_rows: usize,
     pub records_parsed: usize,
 }
 
 impl Clone for ParsedContent {
     fn clone(&self) -> ParsedContent {
         ParsedContent {
+            candidate_keys: (*self).candidate_keys.clone(),
             columns: (*self).columns.clone(),
-            content: (*self).content.clone(),
+            dialect: (*self).dialect.clone(),
+            distinct_values: (*self).distinct_values.clone(),
             errors: (*self).errors.clone(),
             file_name: (*self).file_name.clone(),
+            has_headers: (*self).has_headers,
+            keys_unchecked: (*self).keys_unchecked,
+            ragged_rows: (*self).ragged_rows,
             records_parsed: (*self).records_parsed,
         }
     }
@@ -163,10 +630,15 @@ impl Clone for ParsedContent {
 impl Default for ParsedContent {
     fn default() -> ParsedContent {
         ParsedContent {
+            candidate_keys: Vec::new(),
             columns: Vec::new(),
-            content: Vec::new(),
+            dialect: Dialect::default(),
+            distinct_values: Vec::new(),
             errors: Vec::new(),
             file_name: String::new(),
+            has_headers: true,
+            keys_unchecked: false,
+            ragged_rows: 0,
             records_parsed: 0,
         }
     }
@@ -174,31 +646,34 @@ impl Default for ParsedContent {
 
 impl ParsedContent {
 
-    pub fn new(cols: Vec<ColumnDef>, content: Vec<StringRecord>, errors: Vec<String>, file_name: String, num_lines: usize) -> Self {
+    pub fn new(cols: Vec<ColumnDef>, errors: Vec<String>, file_name: String, num_lines: usize) -> Self {
         ParsedContent {
+            candidate_keys: Vec::new(),
             columns: cols,
-            content,
+            dialect: Dialect::default(),
+            distinct_values: Vec::new(),
             errors,
             file_name,
+            has_headers: true,
+            keys_unchecked: false,
+            ragged_rows: 0,
             records_parsed: num_lines,
         }
     }
 
-    // goes through the column's proposed data types,
+    // goes through the column's proposed data types and merges them into
+    // the one type that can hold all of the column's values
     // DataTypes::String trumps all other data types
-    // DataTypes::F64 is second in line
-    // DataTypes::I64 is next
+    // DataTypes::F64 holds DataTypes::I64 values
+    // DataTypes::DateTime holds DataTypes::Date values
     // if a column ends with DataTypes::Empty will be
     // changed to DataTypes::String
     pub fn set_column_data_types(&mut self) {
         for idx in 0..self.columns.len() {
-            if self.columns[idx].potential_types.contains(&DataTypes::String) {
-                self.columns[idx].data_type = DataTypes::String;
-            } else if self.columns[idx].potential_types.contains(&DataTypes::F64) {
-                self.columns[idx].data_type = DataTypes::F64;
-            } else if self.columns[idx].potential_types.contains(&DataTypes::I64) {
-                self.columns[idx].data_type = DataTypes::I64;
-            }
+            self.columns[idx].data_type = self.columns[idx].potential_types.iter()
+                .fold(DataTypes::Empty, |data_type, potential_type| data_type.merge(*potential_type));
+            self.columns[idx].nullable = self.columns[idx].potential_types.contains(&DataTypes::Empty);
+
             // I'm here and the data type is still empty then there's no other option but to default
             // it to string
             if self.columns[idx].data_type == DataTypes::Empty {
@@ -211,8 +686,7 @@ impl ParsedContent {
 #[cfg(test)]
 mod tests {
     use barrel::types::BaseType;
-    use csv::{StringRecord};
-    use crate::{ColumnDef, DataTypes, ParsedContent};
+    use crate::{static_str, ColumnDef, DataTypes, Dialect, ParsedContent, TransactionMode, Trim};
 
     //==================================================
     // DataTypes tests
@@ -236,6 +710,24 @@ mod tests {
         assert_eq!(DataTypes::String.to_database_type(), BaseType::Text);
     }
 
+    #[test]
+    fn data_types_to_database_type_new_types() {
+        assert_eq!(DataTypes::Bool.to_database_type(), BaseType::Boolean);
+        assert_eq!(DataTypes::Date.to_database_type(), BaseType::Custom("DATE"));
+        assert_eq!(DataTypes::DateTimeTz.to_database_type(), BaseType::Custom("TIMESTAMP WITH TIME ZONE"));
+        assert_eq!(DataTypes::Uuid.to_database_type(), BaseType::Custom("UUID"));
+    }
+
+    #[test]
+    fn data_types_merge() {
+        assert_eq!(DataTypes::I64.merge(DataTypes::F64), DataTypes::F64);
+        assert_eq!(DataTypes::Date.merge(DataTypes::DateTime), DataTypes::DateTime);
+        assert_eq!(DataTypes::DateTimeTz.merge(DataTypes::Date), DataTypes::DateTimeTz);
+        assert_eq!(DataTypes::Empty.merge(DataTypes::Bool), DataTypes::Bool);
+        assert_eq!(DataTypes::Bool.merge(DataTypes::I64), DataTypes::String);
+        assert_eq!(DataTypes::DateTime.merge(DataTypes::DateTimeTz), DataTypes::String);
+    }
+
     #[test]
     fn data_type_to_str_empty() {
         assert_eq!(DataTypes::Empty.to_str(), "");
@@ -256,14 +748,63 @@ mod tests {
         assert_eq!(DataTypes::String.to_str(), "String");
     }
 
+    //==================================================
+    // Dialect tests
+    #[test]
+    fn dialect_parse_char_with_single_char() {
+        assert_eq!(Dialect::parse_char("|").unwrap(), b'|');
+    }
+
+    #[test]
+    fn dialect_parse_char_with_tab() {
+        assert_eq!(Dialect::parse_char("tab").unwrap(), b'\t');
+        assert_eq!(Dialect::parse_char("\\t").unwrap(), b'\t');
+    }
+
+    #[test]
+    fn dialect_parse_char_with_multiple_chars_returns_error() {
+        assert!(Dialect::parse_char("||").is_err());
+    }
+
+    #[test]
+    fn dialect_display() {
+        let mut dialect = Dialect::default();
+        dialect.delimiter = b'\t';
+        dialect.escape = Some(b'\\');
+        assert_eq!(format!("{}", dialect), "delimiter '\\t', quote '\"', escape '\\\\'");
+    }
+
+    #[test]
+    fn trim_from_str() {
+        assert_eq!("all".parse::<Trim>().unwrap(), Trim::All);
+        assert_eq!("Headers".parse::<Trim>().unwrap(), Trim::Headers);
+        assert!("sides".parse::<Trim>().is_err());
+    }
+
+    #[test]
+    fn transaction_mode_from_str() {
+        assert_eq!("file".parse::<TransactionMode>().unwrap(), TransactionMode::File);
+        assert_eq!("Run".parse::<TransactionMode>().unwrap(), TransactionMode::Run);
+        assert_eq!("500".parse::<TransactionMode>().unwrap(), TransactionMode::Rows(500));
+        assert!("0".parse::<TransactionMode>().is_err());
+        assert!("table".parse::<TransactionMode>().is_err());
+    }
+
     //==================================================
     // ColumnDef tests
     #[test]
     fn is_data_type_change_with_empty_dt() {
         let cd = ColumnDef{
             data_type: DataTypes::Empty,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             name: String::from("mycol"),
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
         };
 
         assert_eq!(cd.is_data_type_changeable(), true);
@@ -273,8 +814,15 @@ mod tests {
     fn is_data_type_change_with_i64_dt() {
         let cd = ColumnDef{
             data_type: DataTypes::I64,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             name: String::from("mycol"),
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
         };
 
         assert_eq!(cd.is_data_type_changeable(), true);
@@ -285,8 +833,15 @@ mod tests {
     fn is_data_type_change_with_f64_dt() {
         let cd = ColumnDef{
             data_type: DataTypes::F64,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             name: String::from("mycol"),
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
         };
 
         assert_eq!(cd.is_data_type_changeable(), false);
@@ -296,13 +851,73 @@ mod tests {
     fn is_data_type_change_with_string_dt() {
         let cd = ColumnDef{
             data_type: DataTypes::String,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             name: String::from("mycol"),
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
         };
 
         assert_eq!(cd.is_data_type_changeable(), false);
     }
 
+    #[test]
+    fn to_database_type_sizes_integers_to_their_range() {
+        let mut cd = ColumnDef::default();
+        cd.data_type = DataTypes::I64;
+        cd.stats.min_int = Some(-5);
+        cd.stats.max_int = Some(30000);
+        assert_eq!(cd.to_database_type(), BaseType::Custom("SMALLINT"));
+
+        cd.stats.max_int = Some(40000);
+        assert_eq!(cd.to_database_type(), BaseType::Integer);
+
+        cd.stats.min_int = Some(-3_000_000_000);
+        assert_eq!(cd.to_database_type(), BaseType::Custom("BIGINT"));
+    }
+
+    #[test]
+    fn to_database_type_with_exact_and_inexact_decimals() {
+        let mut cd = ColumnDef::default();
+        cd.data_type = DataTypes::F64;
+        cd.stats.int_digits = 6;
+        cd.stats.scale = 2;
+        assert_eq!(cd.to_database_type(), BaseType::Custom("NUMERIC(8,2)"));
+
+        cd.stats.scale = 17;
+        assert_eq!(cd.to_database_type(), BaseType::Double);
+
+        cd.stats.scale = 2;
+        cd.stats.inexact = true;
+        assert_eq!(cd.to_database_type(), BaseType::Double);
+    }
+
+    #[test]
+    fn static_str_reuses_the_sized_types() {
+        let first = static_str(String::from("NUMERIC(9,3)"));
+        let second = static_str(String::from("NUMERIC(9,3)"));
+        assert_eq!(first, "NUMERIC(9,3)");
+        assert!(std::ptr::eq(first, second));
+    }
+
+    #[test]
+    fn to_database_type_with_short_and_long_strings() {
+        let mut cd = ColumnDef::default();
+        cd.data_type = DataTypes::String;
+        cd.stats.max_length = 12;
+        assert_eq!(cd.to_database_type(), BaseType::Varchar(12));
+
+        cd.stats.max_length = 300;
+        assert_eq!(cd.to_database_type(), BaseType::Text);
+
+        cd.stats.max_length = 0;
+        assert_eq!(cd.to_database_type(), BaseType::Text);
+    }
+
     #[test]
     fn set_column_data_types_with_empty_values_should_give_string_data_type() {
         let mut pc = ParsedContent::default();
@@ -315,6 +930,21 @@ mod tests {
         assert_eq!(pc.columns[0].data_type, DataTypes::String);
     }
 
+    #[test]
+    fn set_column_data_types_makes_columns_with_empty_values_nullable() {
+        let mut pc = ParsedContent::default();
+        let mut col_def = ColumnDef::default();
+
+        col_def.potential_types = vec![DataTypes::I64, DataTypes::Empty];
+        pc.columns.push(col_def.clone());
+        col_def.potential_types = vec![DataTypes::I64];
+        pc.columns.push(col_def);
+        pc.set_column_data_types();
+
+        assert_eq!(pc.columns[0].nullable, true);
+        assert_eq!(pc.columns[1].nullable, false);
+    }
+
     #[test]
     fn set_column_data_types_should_be_string_when_at_least_1_potential_type_is_string() {
         let mut pc = ParsedContent::default();
@@ -353,17 +983,27 @@ mod tests {
     }
 
 
+    #[test]
+    fn set_column_data_types_should_be_datetime_when_dates_and_datetimes_are_mixed() {
+        let mut pc = ParsedContent::default();
+        let mut col_def = ColumnDef::default();
+
+        col_def.potential_types = vec![DataTypes::Date, DataTypes::Empty, DataTypes::DateTime];
+        pc.columns.push(col_def);
+        pc.set_column_data_types();
+
+        assert_eq!(pc.columns[0].data_type, DataTypes::DateTime);
+    }
+
     #[test]
     fn new() {
-        let cols: Vec<ColumnDef> = vec![ColumnDef{name: String::from("test"), data_type: DataTypes::String, potential_types: Vec::new()}];
+        let cols: Vec<ColumnDef> = vec![ColumnDef{name: String::from("test"), data_type: DataTypes::String, nullable: true, header: None, primary_key: false, references: None, unique: false, potential_types: Vec::new(), type_reason: None, stats: Default::default()}];
         let cols_len = cols.len();
-        let content: Vec<StringRecord> = vec![StringRecord::new()];
         let file_name = "my-file".to_string();
         let num_lines = 22;
 
-        let pc = ParsedContent::new(cols, content.clone(), Vec::new(),  file_name.clone(), num_lines);
+        let pc = ParsedContent::new(cols, Vec::new(),  file_name.clone(), num_lines);
         assert_eq!(cols_len, pc.columns.len());
-        assert_eq!(content.len(), pc.content.len());
         assert_eq!(file_name, pc.file_name);
         assert_eq!(num_lines, pc.records_parsed);
     }
@@ -9,10 +9,16 @@ use csv_to::cmd::db;
 use csv_to::cmd::db::{
     DbApp,
     Types,
-    config::Config,
+    config::{Config, DbOpts},
 };
+use csv_to::{Dialect, RaggedRows, TransactionMode, Trim};
 use csv_to::parsers::csv::CSVService;
+use csv_to::parsers::headers::HeaderCase;
+use csv_to::parsers::rows::RowFilter;
+use csv_to::parsers::stdin;
+use csv_to::parsers::values::{DateFormat, NullValues, NumberFormat, ValueParser};
 use csv_to::storage::{
+    self,
     mysql::MySqlStore,
     postgres::PostgresStore,
     sqlite::SQLiteStore
@@ -28,20 +34,95 @@ fn main() {
     // As I build out the sub-commands this match will have multiple options, all of which will
     match opt {
         CsvTo::Db { extension, files, directories, db_type, connection_info, delete_data, name, drop_stores,
-                    no_headers, one_table, save_cache} => {
+                    no_headers, one_table, save_cache, comment, delimiter, escape, no_double_quote, quote,
+                    sniff, trim, encoding, date_formats, timezone, utc, numeric_columns, locale, strip_currency,
+                    strip_percent, nullable, null_values, empty_as_null, primary_key, auto_primary_key,
+                    foreign_keys, header_case, ragged_rows, header_row, skip_lines, skip_footer,
+                    skip_pattern, header_files, no_header_files, batch_size, bulk_load, transaction_mode,
+                    max_errors, fixed_width_ids} => {
 
-            if files.is_empty() && directories.is_empty() {
-                eprintln!("error: either -f, --files or -d, --directories must be provided");
+            if bulk_load && !db_type.has_bulk_loader() {
+                eprintln!("error: --bulk-load is only supported with postgres and mysql");
                 std::process::exit(exitcode::USAGE);
             }
 
-            let config_svc = Config::new(extension, files, directories, db_type.clone(),
-                                         connection_info.clone(), name, drop_stores,
-                                         no_headers, one_table, save_cache, delete_data);
+            // csv-to reads the CSV data piped to it when no files or directories are given
+            let mut files = files;
+            if files.is_empty() && directories.is_empty() {
+                if !stdin::is_piped() {
+                    eprintln!("error: either -f, --files or -d, --directories must be provided, or CSV data piped in");
+                    std::process::exit(exitcode::USAGE);
+                }
+                files.push(PathBuf::from(stdin::PATH));
+            }
+
+            // the options that aren't given keep the default dialect's characters
+            let default_dialect = Dialect::default();
+            let dialect = Dialect {
+                comment,
+                delimiter: delimiter.unwrap_or(default_dialect.delimiter),
+                double_quote: !no_double_quote,
+                escape,
+                quote: quote.unwrap_or(default_dialect.quote),
+                trim: trim.unwrap_or(default_dialect.trim),
+            };
+
+            let number_format = NumberFormat {
+                strip_currency,
+                strip_percent,
+                ..locale.unwrap_or_default()
+            };
+            let mut nulls = NullValues::default();
+            if !null_values.is_empty() {
+                nulls.values = null_values;
+            }
+            nulls.empty_strings = empty_as_null;
+            let value_parser = ValueParser::new(date_formats, timezone, utc, number_format, nulls);
+
+            // the rows above the header row are read as CSV after the skipped lines
+            let row_filter = RowFilter {
+                skip_lines: skip_lines.unwrap_or_default(),
+                skip_rows: header_row.map_or(0, |row| row.saturating_sub(1)),
+                skip_footer: skip_footer.unwrap_or_default(),
+                skip_pattern,
+            };
+
+            let config_svc = Config::new(DbOpts {
+                auto_primary_key,
+                batch_size: batch_size.unwrap_or(storage::BATCH_SIZE),
+                bulk_load,
+                connection_info: connection_info.clone(),
+                db_type: db_type.clone(),
+                delete_data,
+                dialect,
+                directories,
+                drop_tables: drop_stores,
+                encoding,
+                extension,
+                files,
+                fixed_width_ids,
+                foreign_keys,
+                header_case: header_case.unwrap_or_default(),
+                header_files,
+                max_errors,
+                name,
+                no_header_files,
+                no_headers,
+                nullable,
+                numeric_columns,
+                one_table,
+                primary_key,
+                ragged_rows: ragged_rows.unwrap_or_default(),
+                row_filter,
+                save_cache,
+                sniff_dialect: sniff,
+                transaction_mode: transaction_mode.unwrap_or_default(),
+                value_parser,
+            });
 
             match db_type {
                 Types::MySQL => {
-                    let conn = Pool::new(connection_info).unwrap_or_else(|err| {
+                    let conn = Pool::new(connection_info).and_then(|pool| pool.get_conn()).unwrap_or_else(|err| {
                         eprintln!("ERROR: {}", err);
                         std::process::exit(exitcode::IOERR);
                     });
@@ -99,37 +180,139 @@ fn main() {
 pub enum CsvTo {
     #[structopt(name = "db", about = "creates and loads a database from CSV file(s)")]
     Db {
+        #[structopt(long = "auto-primary-key", help = "Makes the first column, or pair of columns, whose values are all different and never empty the primary key of each table when --primary-key isn't given. The other columns with all different values are made UNIQUE")]
+        auto_primary_key: bool,
+
+        #[structopt(long = "batch-size", help = "The most records inserted with a single statement, 1000 when not given. Batches are made smaller when the database can't take that many values or bytes in one statement. A batch that fails is inserted one record at a time")]
+        batch_size: Option<usize>,
+
+        #[structopt(long = "bulk-load", help = "Loads the records with the database's bulk loader, COPY FROM STDIN with Postgres or LOAD DATA LOCAL INFILE with MySQL, which is much faster than inserts. When a file can't be bulk loaded its records are inserted to find the ones that fail. MySQL's records are written to a temporary file first, it needs about as much free disk space as the file being loaded")]
+        bulk_load: bool,
+
+        #[structopt(long = "comment", parse(try_from_str = "Dialect::parse_char"), help = "Lines that start with this character are ignored")]
+        comment: Option<u8>,
+
         #[structopt(short = "c", long = "connection-info", help = "Database connectivity information")]
         connection_info: String,
 
         #[structopt(short = "t", long = "type", help = "The type of database to create, valid types are sqlite, postgres, and mysql")]
         db_type: db::Types,
 
+        #[structopt(long = "date-format", help = "Additional date/time formats to recognize, strftime patterns like %m/%d/%Y, or rfc2822, rfc3339, or epoch. ISO-8601 dates and times are always recognized")]
+        date_formats: Vec<DateFormat>,
+
         #[structopt(long = "delete-data", help = "deletes the data from the tables, keeps the table's schema")]
         delete_data: bool,
 
+        #[structopt(long = "delimiter", parse(try_from_str = "Dialect::parse_char"), help = "The field delimiter, use 'tab' for tab delimited files. A comma when not given")]
+        delimiter: Option<u8>,
+
         #[structopt(short = "d", parse(from_os_str), long = "directories", help = "The directories that contain CSV files to be processed, a comma delimited string of paths")]
         directories: Vec<PathBuf>,
 
         #[structopt(long = "drop-stores", help = "Drops tables/collections if the already exist")]
         drop_stores: bool,
 
-        #[structopt(short = "e", long = "extension", help = "the file extension for the CSV files to be parsed", default_value = "csv")]
+        #[structopt(long = "encoding", parse(try_from_str = "csv_to::parsers::encoding::for_label"), help = "The character encoding of the CSV file(s), e.g. utf-8, latin1, or utf-16le, detected for each file when not given")]
+        encoding: Option<&'static encoding_rs::Encoding>,
+
+        #[structopt(long = "escape", parse(try_from_str = "Dialect::parse_char"), help = "The character used to escape quotes inside of quoted fields")]
+        escape: Option<u8>,
+
+        #[structopt(long = "empty-as-null", help = "Stores empty values in string columns as NULL instead of empty strings, empty values in other columns are always NULL")]
+        empty_as_null: bool,
+
+        #[structopt(short = "e", long = "extension", help = "the file extension for the CSV files to be parsed, can be a comma delimited list of extensions", default_value = "csv")]
         extension: String,
 
-        #[structopt(short = "f", parse(from_os_str), long = "files", help = "The CSV files to be processed, can be /path/to/files/ or a comma delimited string of paths")]
+        #[structopt(short = "f", parse(from_os_str), long = "files", help = "The CSV files to be processed, can be /path/to/files/ or a comma delimited string of paths, use - to read from stdin")]
         files: Vec<PathBuf>,
 
-        #[structopt(short = "n", long = "name", help = "Name of the database to be created")]
+        #[structopt(long = "fixed-width-ids", help = "Stores the columns whose values are all the same number of digits, 5 or more, as strings like phone numbers. Without it only values with leading zeros or too large for a 64-bit integer are stored as strings")]
+        fixed_width_ids: bool,
+
+        #[structopt(long = "foreign-keys", help = "Creates FOREIGN KEY constraints for the columns whose values are all in the primary key or a UNIQUE column of another file's table, the tables they refer to are loaded first")]
+        foreign_keys: bool,
+
+        #[structopt(long = "header-case", help = "The case of the column names made from the headers, valid cases are keep, snake, and camel, keep when not given. Accented letters lose their accents, other characters that can't be in a name become underscores and repeated names are numbered")]
+        header_case: Option<HeaderCase>,
+
+        #[structopt(long = "header-files", raw(use_delimiter = "true"), help = "Files that have a header row, their first row isn't checked, a comma delimited list of file names or paths")]
+        header_files: Vec<String>,
+
+        #[structopt(long = "header-row", help = "The row the header is on, counted from 1 after the lines skipped with --skip-lines, the rows above it are skipped. The first row of data when there are no headers")]
+        header_row: Option<usize>,
+
+        #[structopt(long = "locale", parse(try_from_str = "NumberFormat::for_locale"), help = "The locale numbers are written in, e.g. en_US for 1,234.56 or de_DE for 1.234,56. Without it numbers have no thousands separators and a . before the decimals")]
+        locale: Option<NumberFormat>,
+
+        #[structopt(long = "max-errors", help = "The most records of a transaction that can fail to be stored, the transaction is rolled back when more of its records fail so a rerun starts clean. Without it a transaction is only rolled back when loading stops with an error")]
+        max_errors: Option<usize>,
+
+        #[structopt(short = "n", long = "name", help = "Name of the database to be created, also the table name for data read from stdin")]
         name: String,
 
-        #[structopt(long = "no-headers", help = "The CSV file(s) have no column headers")]
+        #[structopt(long = "no-header-files", raw(use_delimiter = "true"), help = "Files that have no header row, their first row isn't checked, a comma delimited list of file names or paths")]
+        no_header_files: Vec<String>,
+
+        #[structopt(long = "no-headers", help = "The CSV file(s) have no column headers. Without it each file's first row is a header unless its values look like the values of the rows that follow it")]
         no_headers: bool,
 
+        #[structopt(long = "no-double-quote", help = "Two quote characters inside of a quoted field are not read as a single quote")]
+        no_double_quote: bool,
+
+        #[structopt(long = "null-values", raw(use_delimiter = "true"), help = "The values that are stored as NULL, a comma delimited list. NA, NULL, \\N, and - when not given")]
+        null_values: Vec<String>,
+
+        #[structopt(long = "nullable", help = "Creates every column as nullable, otherwise columns that never have an empty value are NOT NULL")]
+        nullable: bool,
+
+        #[structopt(long = "numeric-columns", raw(use_delimiter = "true"), help = "Columns to store as numbers even when their values look like identifiers, values with leading zeros or, with --fixed-width-ids, that are all the same number of digits are stored as strings otherwise, a comma delimited list of column names")]
+        numeric_columns: Vec<String>,
+
         #[structopt(short = "o", long = "one-table", help = "Store the parsed data in a single table with the name given here")]
         one_table: Option<String>,
 
+        #[structopt(long = "primary-key", raw(use_delimiter = "true"), help = "The columns of each table's primary key, a comma delimited list of column names")]
+        primary_key: Vec<String>,
+
+        #[structopt(long = "quote", parse(try_from_str = "Dialect::parse_char"), help = "The character used to quote fields. A double quote when not given")]
+        quote: Option<u8>,
+
+        #[structopt(long = "ragged-rows", help = "What is done with rows that have more or fewer fields than the header, valid options are strict, pad, truncate, and side-file, strict when not given. strict skips them, pad stores missing fields as NULL, truncate also drops extra fields and side-file writes them to a .bad.csv file next to the input")]
+        ragged_rows: Option<RaggedRows>,
+
         #[structopt(short = "s", long = "save-cache", help = "Stores the meta data about each input's column data definitions")]
         save_cache: bool,
+
+        #[structopt(long = "skip-footer", help = "The number of rows at the end of each file that are dropped, like totals or a generated on line")]
+        skip_footer: Option<usize>,
+
+        #[structopt(long = "skip-lines", help = "The number of lines at the beginning of each file that are skipped before the header, like titles or notes, the lines don't have to be CSV")]
+        skip_lines: Option<usize>,
+
+        #[structopt(long = "skip-pattern", parse(try_from_str = "regex::Regex::new"), help = "Rows that match this regular expression are dropped, the row's fields are matched as they are in the file joined by the delimiter, e.g. ^Total,")]
+        skip_pattern: Option<regex::Regex>,
+
+        #[structopt(long = "sniff", help = "Detects each file's delimiter, quoting and header row from a sample of the file, the ones saved in the cache by an earlier run are used when there are any")]
+        sniff: bool,
+
+        #[structopt(long = "strip-currency", help = "Removes currency symbols like $ and € before or after numbers so they are stored as numbers")]
+        strip_currency: bool,
+
+        #[structopt(long = "strip-percent", help = "Removes the percent sign after numbers so they are stored as numbers, 45% is stored as 45")]
+        strip_percent: bool,
+
+        #[structopt(long = "timezone", help = "The timezone of dates and times that don't have one, e.g. America/New_York or UTC. Without it they are stored without a timezone")]
+        timezone: Option<chrono_tz::Tz>,
+
+        #[structopt(long = "transaction", help = "How the tables and records are committed, valid modes are file, run, or a number of records, file when not given. file commits each file's table and records together, run commits every file together at the end and a number commits each file's records every that many records")]
+        transaction_mode: Option<TransactionMode>,
+
+        #[structopt(long = "trim", help = "Whitespace to trim, valid options are none, headers, fields, and all, none when not given")]
+        trim: Option<Trim>,
+
+        #[structopt(long = "utc", help = "Converts dates and times with a timezone to UTC before they are stored")]
+        utc: bool,
     }
 }
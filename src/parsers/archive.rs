@@ -0,0 +1,274 @@
+//! Lists the files inside of zip and tar archives and reads them straight from the
+//! archive without extracting them to disk
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use bzip2::read::BzDecoder;
+use flate2::read::DeflateDecoder;
+use tar::Archive;
+use zip::{CompressionMethod, ZipArchive};
+
+use super::compression::Compression;
+
+/// the file extensions of the supported archive formats
+pub const EXTENSIONS: [&str; 10] = ["zip", "tar", "tgz", "tbz2", "txz", "tzst", "tar.gz", "tar.bz2", "tar.xz", "tar.zst"];
+
+/// How the data of an archive member is stored in its archive
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MemberFormat {
+    Tar,
+    ZipBzip2,
+    ZipDeflated,
+    ZipStored,
+}
+
+/// A file inside of an archive
+/// name: the path of the file inside of the archive
+/// offset: where the file's data starts, for tar archives this is the position in the
+/// decompressed archive
+/// size: the number of bytes of the file's data in the archive
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchiveMember {
+    pub format: MemberFormat,
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl ArchiveMember {
+    /// returns a reader of the member's content. Zip members and the members of uncompressed
+    /// tar archives are read starting at their offset. Compressed tar archives can't be
+    /// seeked so they're decompressed from the start up to the member's offset, each time a
+    /// member is opened the archive is decompressed up to it
+    pub fn open(&self, archive_path: &str) -> io::Result<Box<dyn Read>> {
+        let mut file = BufReader::new(File::open(archive_path)?);
+
+        if self.format == MemberFormat::Tar {
+            let compression = Compression::detect(archive_path, file.fill_buf()?);
+            if compression != Compression::None {
+                let mut content = compression.decoder(file)?;
+                io::copy(&mut (&mut content).take(self.offset), &mut io::sink())?;
+                return Ok(Box::new(content.take(self.size)));
+            }
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let data = file.take(self.size);
+        match self.format {
+            MemberFormat::ZipBzip2 => Ok(Box::new(BzDecoder::new(data))),
+            MemberFormat::ZipDeflated => Ok(Box::new(DeflateDecoder::new(data))),
+            _ => Ok(Box::new(data)),
+        }
+    }
+}
+
+/// returns true if the path has the extension of a supported archive format
+pub fn is_archive(path: &str) -> bool {
+    let lower_path = path.to_lowercase();
+    EXTENSIONS.iter().any(|ext| lower_path.ends_with(&format!(".{}", ext)))
+}
+
+/// returns the files inside of the archive whose names are wanted, directories and other
+/// entries that aren't regular files are left out. The wanted files that can't be read are
+/// skipped and returned with the reason why
+pub fn list_members<F: Fn(&str) -> bool>(archive_path: &str, wanted: F) -> Result<(Vec<ArchiveMember>, Vec<String>), failure::Error> {
+    if archive_path.to_lowercase().ends_with(".zip") {
+        list_zip_members(archive_path, wanted)
+    } else {
+        list_tar_members(archive_path, wanted).map(|members| (members, Vec::new()))
+    }
+}
+
+fn list_zip_members<F: Fn(&str) -> bool>(archive_path: &str, wanted: F) -> Result<(Vec<ArchiveMember>, Vec<String>), failure::Error> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let mut members: Vec<ArchiveMember> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+
+    for idx in 0..archive.len() {
+        // the member is looked at without decompressing it first, the compressed members that
+        // can't be read are only an error when they're wanted
+        let (name, is_dir) = {
+            let raw = archive.by_index_raw(idx)?;
+            (raw.name().to_string(), raw.is_dir())
+        };
+        if is_dir || !wanted(&name) {
+            continue;
+        }
+
+        let file = match archive.by_index(idx) {
+            Ok(file) => file,
+            Err(e) => {
+                skipped.push(format!("{}: '{}' was skipped -> {}", archive_path, name, e));
+                continue;
+            },
+        };
+
+        let format = match file.compression() {
+            CompressionMethod::Stored => MemberFormat::ZipStored,
+            CompressionMethod::Deflated => MemberFormat::ZipDeflated,
+            CompressionMethod::Bzip2 => MemberFormat::ZipBzip2,
+            method => {
+                skipped.push(format!("{}: '{}' was skipped -> unsupported compression method {:?}", archive_path, name, method));
+                continue;
+            },
+        };
+
+        members.push(ArchiveMember {
+            format,
+            name: file.name().to_string(),
+            offset: file.data_start(),
+            size: file.compressed_size(),
+        });
+    }
+
+    Ok((members, skipped))
+}
+
+fn list_tar_members<F: Fn(&str) -> bool>(archive_path: &str, wanted: F) -> Result<Vec<ArchiveMember>, failure::Error> {
+    let mut file = BufReader::new(File::open(archive_path)?);
+    let compression = Compression::detect(archive_path, file.fill_buf()?);
+    let mut archive = Archive::new(compression.decoder(file)?);
+    let mut members: Vec<ArchiveMember> = Vec::new();
+
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        if !entry.header().entry_type().is_file() || !wanted(&name) {
+            continue;
+        }
+
+        members.push(ArchiveMember {
+            format: MemberFormat::Tar,
+            name,
+            offset: entry.raw_file_position(),
+            size: entry.header().size()?,
+        });
+    }
+
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn is_archive_with_archives() {
+        assert!(is_archive("/tmp/bundle.zip"));
+        assert!(is_archive("/tmp/bundle.TAR.GZ"));
+        assert!(is_archive("/tmp/bundle.tgz"));
+    }
+
+    #[test]
+    fn is_archive_with_csv_files() {
+        assert!(!is_archive("/tmp/data.csv"));
+        assert!(!is_archive("/tmp/data.csv.gz"));
+    }
+
+    #[test]
+    fn list_members_and_open_with_zip_archive() {
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let archive_path = tmp_dir.path().join("bundle.zip").into_os_string().into_string().unwrap();
+
+        let mut zip = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        zip.add_directory("orders/", zip::write::FileOptions::default()).unwrap();
+        zip.start_file("orders/2019.csv", zip::write::FileOptions::default()).unwrap();
+        zip.write_all(b"id,total\n1,2.5\n").unwrap();
+        zip.start_file("customers.csv", zip::write::FileOptions::default().compression_method(CompressionMethod::Stored)).unwrap();
+        zip.write_all(b"id,name\n1,rob\n").unwrap();
+        zip.finish().unwrap();
+
+        let (members, skipped) = list_members(&archive_path, |_| true).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].name, "orders/2019.csv");
+        assert_eq!(members[0].format, MemberFormat::ZipDeflated);
+        assert_eq!(members[1].format, MemberFormat::ZipStored);
+
+        let mut content = String::new();
+        members[0].open(&archive_path).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "id,total\n1,2.5\n");
+
+        content.clear();
+        members[1].open(&archive_path).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "id,name\n1,rob\n");
+    }
+
+    #[test]
+    fn list_members_skips_the_members_that_use_unsupported_compression() {
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let archive_path = tmp_dir.path().join("bundle.zip").into_os_string().into_string().unwrap();
+
+        let mut zip = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        for name in &["README.pdf", "legacy.csv", "data.csv"] {
+            zip.start_file(*name, zip::write::FileOptions::default().compression_method(CompressionMethod::Stored)).unwrap();
+            zip.write_all(b"id,name\n1,rob\n").unwrap();
+        }
+        zip.finish().unwrap();
+
+        // README.pdf and legacy.csv are changed to say they're compressed with LZMA
+        let mut bytes = std::fs::read(&archive_path).unwrap();
+        for (signature, method_offset) in &[(b"PK\x03\x04", 8), (b"PK\x01\x02", 10)] {
+            let headers: Vec<usize> = (0..bytes.len() - 4).filter(|idx| &bytes[*idx..*idx + 4] == *signature).take(2).collect();
+            for idx in headers {
+                bytes[idx + method_offset] = 14;
+            }
+        }
+        std::fs::write(&archive_path, bytes).unwrap();
+
+        let (members, skipped) = list_members(&archive_path, |name| name.ends_with(".csv")).unwrap();
+        assert_eq!(members.iter().map(|m| m.name.as_str()).collect::<Vec<&str>>(), vec!["data.csv"]);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].contains("'legacy.csv' was skipped"), "{}", skipped[0]);
+    }
+
+    const TAR_MEMBERS: [(&str, &str); 4] = [("orders.csv", "id,total\n1,2.5\n"), ("customers.csv", "id,name\n1,rob\n"),
+                                           ("products.csv", "sku,price\nA1,9.99\n"), ("stores.csv", "id,city\n7,Raleigh\n")];
+
+    fn append_members<W: Write>(builder: &mut tar::Builder<W>, members: &[(&str, &str)]) {
+        for (name, content) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, content.as_bytes()).unwrap();
+        }
+    }
+
+    // opens the members last to first so each one is found by its offset and not by
+    // where the previous member ended
+    fn assert_members_open(archive_path: &str) {
+        let (members, _) = list_members(archive_path, |_| true).unwrap();
+        assert_eq!(members.len(), TAR_MEMBERS.len());
+        for (member, (name, expected)) in members.iter().zip(TAR_MEMBERS.iter()).rev() {
+            let mut content = String::new();
+            member.open(archive_path).unwrap().read_to_string(&mut content).unwrap();
+            assert_eq!((member.name.as_str(), content.as_str()), (*name, *expected));
+        }
+    }
+
+    #[test]
+    fn list_members_and_open_with_tar_archive() {
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let archive_path = tmp_dir.path().join("bundle.tar").into_os_string().into_string().unwrap();
+
+        let mut builder = tar::Builder::new(File::create(&archive_path).unwrap());
+        append_members(&mut builder, &TAR_MEMBERS);
+        builder.finish().unwrap();
+
+        assert_members_open(&archive_path);
+    }
+
+    #[test]
+    fn list_members_and_open_with_tar_gz_archive() {
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let archive_path = tmp_dir.path().join("bundle.tar.gz").into_os_string().into_string().unwrap();
+
+        let encoder = flate2::write::GzEncoder::new(File::create(&archive_path).unwrap(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        append_members(&mut builder, &TAR_MEMBERS);
+        builder.into_inner().unwrap().finish().unwrap();
+
+        assert_members_open(&archive_path);
+    }
+}
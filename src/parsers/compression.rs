@@ -0,0 +1,154 @@
+//! Detects compressed inputs and wraps them in a reader that decompresses their content
+use std::io::{self, BufRead, Read};
+use bzip2::read::BzDecoder;
+use flate2::read::MultiGzDecoder;
+use xz2::read::XzDecoder;
+
+/// The compression formats that can be read
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Bzip2,
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+/// the file extensions of the supported compression formats
+pub const EXTENSIONS: [&str; 4] = ["bz2", "gz", "xz", "zst"];
+
+// the extensions of uncompressed text files, their content isn't checked for magic bytes since
+// a header can start with the same bytes as a compressed file
+const PLAIN_EXTENSIONS: [&str; 5] = ["csv", "tsv", "txt", "psv", "tab"];
+
+impl Compression {
+    /// determines the compression from the leading bytes of the content. When the bytes
+    /// don't match a known format the file path's extension is used. Files with the extension
+    /// of an uncompressed text file are never compressed
+    pub fn detect(path: &str, leading_bytes: &[u8]) -> Compression {
+        let lower_path = path.to_lowercase();
+        if PLAIN_EXTENSIONS.iter().any(|ext| lower_path.ends_with(&format!(".{}", ext))) {
+            return Compression::None;
+        }
+
+        match Compression::from_magic_bytes(leading_bytes) {
+            Compression::None => Compression::from_extension(path),
+            compression => compression,
+        }
+    }
+
+    /// returns the compression that matches the extension of the given path
+    pub fn from_extension(path: &str) -> Compression {
+        let lower_path = path.to_lowercase();
+        match lower_path.rsplit('.').next() {
+            Some("bz2") => Compression::Bzip2,
+            Some("gz") => Compression::Gzip,
+            Some("xz") => Compression::Xz,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// returns the compression whose magic number starts the given bytes, a bzip2 magic number
+    /// is followed by the block size from 1 to 9
+    pub fn from_magic_bytes(bytes: &[u8]) -> Compression {
+        if bytes.starts_with(&[0x1F, 0x8B]) {
+            Compression::Gzip
+        } else if bytes.starts_with(b"BZh") && bytes.get(3).map_or(false, |b| (b'1'..=b'9').contains(b)) {
+            Compression::Bzip2
+        } else if bytes.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else if bytes.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// wraps the reader in one that decompresses its content
+    pub fn decoder<R: BufRead + 'static>(self, reader: R) -> io::Result<Box<dyn Read>> {
+        match self {
+            Compression::None => Ok(Box::new(reader)),
+            Compression::Bzip2 => Ok(Box::new(BzDecoder::new(reader))),
+            Compression::Gzip => Ok(Box::new(MultiGzDecoder::new(reader))),
+            Compression::Xz => Ok(Box::new(XzDecoder::new_multi_decoder(reader))),
+            Compression::Zstd => Ok(Box::new(zstd::stream::read::Decoder::new(reader)?)),
+        }
+    }
+}
+
+/// returns the path without its compression extension, data.csv.gz becomes data.csv
+pub fn strip_extension(path: &str) -> &str {
+    match Compression::from_extension(path) {
+        Compression::None => path,
+        _ => &path[..path.rfind('.').unwrap_or_else(|| path.len())],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn from_extension_with_compressed_files() {
+        assert_eq!(Compression::from_extension("data.csv.bz2"), Compression::Bzip2);
+        assert_eq!(Compression::from_extension("data.csv.GZ"), Compression::Gzip);
+        assert_eq!(Compression::from_extension("data.csv.xz"), Compression::Xz);
+        assert_eq!(Compression::from_extension("data.csv.zst"), Compression::Zstd);
+    }
+
+    #[test]
+    fn from_extension_with_uncompressed_file() {
+        assert_eq!(Compression::from_extension("data.csv"), Compression::None);
+    }
+
+    #[test]
+    fn detect_prefers_magic_bytes_over_extension() {
+        assert_eq!(Compression::detect("-", &[0x1F, 0x8B, 0x08]), Compression::Gzip);
+        assert_eq!(Compression::detect("data.csv.xz", b"a,b,c"), Compression::Xz);
+    }
+
+    #[test]
+    fn detect_with_plain_text_extension() {
+        assert_eq!(Compression::detect("data.csv", &[0x1F, 0x8B, 0x08]), Compression::None);
+        assert_eq!(Compression::detect("BZh.TSV", b"BZh9"), Compression::None);
+    }
+
+    #[test]
+    fn from_magic_bytes_with_bzip2_block_size() {
+        assert_eq!(Compression::from_magic_bytes(b"BZh91AY&SY"), Compression::Bzip2);
+        assert_eq!(Compression::from_magic_bytes(b"BZh_code,name\n"), Compression::None);
+        assert_eq!(Compression::from_magic_bytes(b"BZh"), Compression::None);
+    }
+
+    #[test]
+    fn strip_extension_removes_compression_extension() {
+        assert_eq!(strip_extension("/tmp/data.csv.gz"), "/tmp/data.csv");
+        assert_eq!(strip_extension("/tmp/data.csv"), "/tmp/data.csv");
+    }
+
+    #[test]
+    fn decoder_with_gzip_content() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"a,b\n1,2\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let compression = Compression::from_magic_bytes(&compressed);
+        let mut content = String::new();
+        compression.decoder(io::Cursor::new(compressed)).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "a,b\n1,2\n");
+    }
+
+    #[test]
+    fn decoder_with_zstd_content() {
+        let compressed = zstd::stream::encode_all(&b"a,b\n1,2\n"[..], 0).unwrap();
+
+        let compression = Compression::from_magic_bytes(&compressed);
+        assert_eq!(compression, Compression::Zstd);
+
+        let mut content = String::new();
+        compression.decoder(io::Cursor::new(compressed)).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "a,b\n1,2\n");
+    }
+}
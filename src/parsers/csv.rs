@@ -1,35 +1,47 @@
 use failure::{Error};
-use regex::Regex;
-use csv::{Reader, StringRecord};
+use csv::{Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
+use std::fs::File;
+use std::io::Read;
 
-use crate::{ColumnDef, DataTypes, Input, InputSource, ParsedContent};
+use crate::{ColumnDef, DataTypes, Dialect, Input, InputSource, ParsedContent, RaggedRows, Records};
 use super::InputService;
+use super::headers::{self, HeaderFormat};
+use super::identifiers::IdentifierCheck;
+use super::keys::{DistinctValues, KeyCheck};
+use super::sniffer;
+use super::stats::ColumnStats;
+use super::values::{self, ValueParser};
 
+// the number of records from the sample used to decide if the first record is a header
+const HEADER_SAMPLE_RECORDS: usize = 20;
 
-#[derive(Clone,Debug)]
-pub struct CSVService {
-    field_name_regex: Regex,
-}
 
-impl Default for CSVService {
-    fn default() -> CSVService {
-        CSVService {
-            field_name_regex: Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]+$").unwrap(),
-        }
-    }
-}
+#[derive(Clone,Debug,Default)]
+pub struct CSVService {}
 
 impl CSVService {
 
-    fn create_column_defs(&self, headers: &StringRecord) -> Vec<ColumnDef> {
+    // the headers are turned into names that can be used in every database, a column keeps
+    // the header its name was made from when they differ
+    fn create_column_defs(&self, headers: &StringRecord, has_headers: bool, format: &HeaderFormat) -> Vec<ColumnDef> {
         let mut col_defs: Vec<ColumnDef> = Vec::new();
 
-        for (n, header )in  headers.iter().enumerate() {
-            let cleaned_name = self.validate_field_name(n, header, &self.field_name_regex);
+        let names = headers::column_names(headers.iter(), format);
+        for (header, cleaned_name) in headers.iter().zip(names) {
             let cd = ColumnDef {
-                name: cleaned_name.clone(),
+                header: match has_headers && header != cleaned_name {
+                    true => Some(header.to_string()),
+                    false => None,
+                },
+                name: cleaned_name,
                 data_type: DataTypes::Empty,
+                nullable: true,
+                primary_key: false,
+                references: None,
+                unique: false,
                 potential_types: Vec::new(),
+                type_reason: None,
+                stats: ColumnStats::default(),
             };
             col_defs.push(cd);
         }
@@ -37,61 +49,257 @@ impl CSVService {
         col_defs
     }
 
-    fn check_field_data_type(val: &str) -> DataTypes {
-        if val == "" {
-            return DataTypes::Empty;
+    // the reader is flexible so rows with more or fewer fields than the header are handed
+    // back and fit to the header with the input's ragged rows option. The number of lines
+    // skipped before the reader's first line is returned with it
+    fn create_reader(input: &InputSource, dialect: &Dialect, has_headers: bool) -> Result<(Reader<Box<dyn Read>>, u64), Error> {
+        let (file, num_skipped) = CSVService::open(input, dialect)?;
+        Ok((CSVService::reader_builder(dialect, has_headers).flexible(true).from_reader(file), num_skipped))
+    }
+
+    // fits a record with more or fewer fields than the header to the number of columns, missing
+    // fields are filled with the padding. None when the record isn't loaded
+    fn fit_record(record: StringRecord, num_columns: usize, ragged_rows: RaggedRows, padding: &str) -> Option<StringRecord> {
+        if record.len() == num_columns {
+            return Some(record);
         }
 
-        match val.parse::<i64>() {
-            Ok(_) => DataTypes::I64,
-            Err(_) => {
-                match val.parse::<f64>() {
-                    Ok(_) => DataTypes::F64,
-                    Err(_) => {
-                        return DataTypes::String
-                    }
+        match ragged_rows {
+            RaggedRows::Pad | RaggedRows::Truncate if record.len() < num_columns => {
+                let mut padded = record;
+                while padded.len() < num_columns {
+                    padded.push_field(padding);
                 }
+                Some(padded)
+            },
+            RaggedRows::Truncate => Some(record.iter().take(num_columns).collect()),
+            _ => None,
+        }
+    }
+
+    // writes a ragged row to the input's file of bad rows, the file is created with the
+    // header row when the first bad row is written
+    fn write_bad_row(writer: &mut Option<Writer<File>>, input: &InputSource, dialect: &Dialect, headers: Option<&StringRecord>, record: &StringRecord) -> Result<(), Error> {
+        if writer.is_none() {
+            let mut new_writer = WriterBuilder::new()
+                .delimiter(dialect.delimiter)
+                .quote(dialect.quote)
+                .flexible(true)
+                .from_path(input.bad_rows_path())?;
+            if let Some(headers) = headers {
+                new_writer.write_record(headers)?;
             }
+            *writer = Some(new_writer);
+        }
+
+        if let Some(writer) = writer.as_mut() {
+            writer.write_record(record)?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    // opens the input's content after the lines and rows that come before the header, with the
+    // number of lines that were skipped
+    fn open(input: &InputSource, dialect: &Dialect) -> Result<(Box<dyn Read>, u64), Error> {
+        let content = input.row_filter.skip_lines(input.get_reader()?)?;
+        let (content, num_lines) = input.row_filter.skip_rows(content, dialect)?;
+        Ok((content, input.row_filter.skip_lines as u64 + num_lines))
+    }
+
+    fn reader_builder(dialect: &Dialect, has_headers: bool) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder.comment(dialect.comment)
+            .delimiter(dialect.delimiter)
+            .double_quote(dialect.double_quote)
+            .escape(dialect.escape)
+            .has_headers(has_headers)
+            .quote(dialect.quote)
+            .trim(dialect.trim.to_csv_trim());
+        builder
+    }
+
+    // returns the dialect and header setting used to read the input. When the input
+    // should be sniffed, or its header row detected, they are detected from a sample of the
+    // input, if the sample doesn't show whether there is a header row the input's setting is used.
+    // A dialect saved in the cache by an earlier run is used instead of sniffing again
+    fn resolve_dialect(input: &InputSource) -> Result<(Dialect, bool), Error> {
+        if !input.sniff_dialect && !input.detect_headers {
+            return Ok((input.dialect.clone(), input.has_headers));
+        }
+
+        if let Some((cached_dialect, cached_headers)) = &input.cached_dialect {
+            let dialect = match input.sniff_dialect {
+                true => cached_dialect.clone(),
+                false => input.dialect.clone(),
+            };
+            let has_headers = match input.detect_headers {
+                true => *cached_headers,
+                false => input.has_headers,
+            };
+            return Ok((dialect, has_headers));
+        }
+
+        let sample = CSVService::read_sample(input)?;
+        let dialect = match input.sniff_dialect {
+            true => sniffer::sniff_dialect(&sample, &input.dialect),
+            false => input.dialect.clone(),
+        };
+        let has_headers = match input.detect_headers {
+            true => CSVService::sniff_has_headers(&sample, &dialect, &input.value_parser).unwrap_or(input.has_headers),
+            false => input.has_headers,
+        };
+
+        Ok((dialect, has_headers))
+    }
+
+    // reads up to sniffer::SAMPLE_SIZE bytes from the beginning of the input, after the
+    // skipped lines and rows, when the sample doesn't contain the whole input the partial last
+    // line is dropped. The rows are skipped with the input's dialect since it hasn't been sniffed yet
+    fn read_sample(input: &InputSource) -> Result<Vec<u8>, Error> {
+        let mut sample: Vec<u8> = Vec::new();
+        CSVService::open(input, &input.dialect)?.0.take(sniffer::SAMPLE_SIZE as u64).read_to_end(&mut sample)?;
+
+        if sample.len() == sniffer::SAMPLE_SIZE {
+            if let Some(last_newline) = sample.iter().rposition(|&b| b == b'\n') {
+                sample.truncate(last_newline + 1);
+            }
+        }
+
+        Ok(sample)
+    }
+
+    // compares the data types of the first record's fields with the data types of the
+    // fields that follow it. A column whose values are numbers, dates, booleans, etc. but
+    // whose first value is a string is a vote for the first record being a header, any other
+    // first value is a vote against. None is returned when the votes
+    // are tied
+    fn sniff_has_headers(sample: &[u8], dialect: &Dialect, value_parser: &ValueParser) -> Option<bool> {
+        let records: Vec<StringRecord> = CSVService::reader_builder(dialect, false)
+            .flexible(true)
+            .from_reader(sample)
+            .records()
+            .filter_map(|rec| rec.ok())
+            .take(HEADER_SAMPLE_RECORDS)
+            .collect();
+
+        if records.len() < 2 {
+            return None;
+        }
+
+        let mut votes: i32 = 0;
+        for (col_idx, first_value) in records[0].iter().enumerate() {
+            let col_type = records[1..].iter()
+                .filter_map(|rec| rec.get(col_idx))
+                .fold(DataTypes::Empty, |col_type, val| col_type.merge(CSVService::check_field_data_type(val, value_parser)));
+
+            if col_type != DataTypes::String && col_type != DataTypes::Empty {
+                match CSVService::check_field_data_type(first_value, value_parser) {
+                    DataTypes::String => votes += 1,
+                    DataTypes::Empty => (),
+                    _ => votes -= 1,
+                }
+            }
+        }
+
+        match votes {
+            0 => None,
+            v => Some(v > 0),
+        }
+    }
+
+    // numeric columns that hold identifiers are given a String potential type so they keep
+    // their leading zeros and precision, the columns the user listed as numeric are left alone
+    fn keep_identifiers_as_strings(columns: &mut Vec<ColumnDef>, id_checks: &[IdentifierCheck], numeric_columns: &[String], fixed_width_ids: bool) {
+        for (col, id_check) in columns.iter_mut().zip(id_checks) {
+            if numeric_columns.iter().any(|name| col.has_name(name)) {
+                continue;
+            }
+
+            let col_type = col.potential_types.iter().fold(DataTypes::Empty, |t, pt| t.merge(*pt));
+            if col_type != DataTypes::I64 && col_type != DataTypes::F64 {
+                continue;
+            }
+
+            if let Some(reason) = id_check.reason(fixed_width_ids) {
+                col.potential_types.push(DataTypes::String);
+                col.type_reason = Some(reason);
+            }
+        }
+    }
+
+    // the checks go from the most to the least specific type, a field that
+    // matches none of them is a String
+    fn check_field_data_type(val: &str, value_parser: &ValueParser) -> DataTypes {
+        if value_parser.is_null(val) {
+            return DataTypes::Empty;
+        }
+
+        // dates are checked first so dates in epoch seconds aren't read as integers
+        if let Some(date_type) = value_parser.date_type(val) {
+            date_type
+        } else if let Some(number) = value_parser.parse_number(val) {
+            match number.parse::<i64>() {
+                Ok(_) => DataTypes::I64,
+                Err(_) => DataTypes::F64,
+            }
+        } else if values::parse_bool(val).is_some() {
+            DataTypes::Bool
+        } else if values::is_uuid(val) {
+            DataTypes::Uuid
+        } else if values::is_json(val) {
+            DataTypes::Json
+        } else {
+            DataTypes::String
         }
     }
 }
 
 impl InputService for CSVService {
     // pares parses the file uses the data to create an instance of
-    // a ParsedContent struct that with the file's contents along with
-    // a definition of each column in the file
-    fn parse(&self, input: InputSource) -> Result<ParsedContent, Error> {
-        let file = input.get_reader()?;
-        let mut rdr = Reader::from_reader(file);
+    // a ParsedContent struct that with the file's a definition of each
+    // column in the file. The records themselves are not kept, use
+    // records to stream them into a store
+    fn parse(&self, input: &InputSource) -> Result<ParsedContent, Error> {
+        let (dialect, has_headers) = CSVService::resolve_dialect(input)?;
+        let (mut rdr, num_skipped) = CSVService::create_reader(input, &dialect, has_headers)?;
         let mut parsed_content = ParsedContent::default();
-        parsed_content.file_name = input.location;
-        
-        if input.has_headers {
-            match rdr.headers() {
-                Ok(headers) => parsed_content.columns = self.create_column_defs(headers),
-                Err(e) => return Err(failure::err_msg(format!("{}", e)))
-            }
-        } else {
-            let pos = rdr.position().clone();
+        parsed_content.file_name = input.get_name();
+        parsed_content.dialect = dialect;
+        parsed_content.has_headers = has_headers;
 
-            match rdr.headers() {
-                Ok(headers) => {
-                    let num_cols = headers.len();
-                    let mut cols: Vec<String> = Vec::new();
+        // when the file has no headers the reader hands back the first record
+        // without consuming it so it is still parsed in the loop below
+        let mut header_row: Option<StringRecord> = None;
+        match rdr.headers() {
+            Ok(headers) => {
+                if has_headers {
+                    header_row = Some(headers.clone());
+                    parsed_content.columns = self.create_column_defs(headers, true, &input.header_format)
+                } else {
+                    let cols: Vec<String> = (0..headers.len()).map(|idx| format!("col_{}", idx)).collect();
+                    parsed_content.columns = self.create_column_defs(&StringRecord::from(cols), false, &input.header_format)
+                }
+            },
+            Err(e) => return Err(failure::err_msg(format!("{}", e)))
+        }
 
-                    for idx in 0..num_cols {
-                        cols.push(format!("col_{}", idx));
-                    }
+        let mut id_checks: Vec<IdentifierCheck> = vec![IdentifierCheck::default(); parsed_content.columns.len()];
+        let mut key_check = match input.find_keys {
+            true => Some(KeyCheck::new(parsed_content.columns.len())),
+            false => None,
+        };
+        let mut distinct_values = match input.find_references {
+            true => Some(DistinctValues::new(parsed_content.columns.len())),
+            false => None,
+        };
 
-                    parsed_content.columns = self.create_column_defs(&StringRecord::from(cols))
-                },
-                Err(e) => return Err(failure::err_msg(format!("{}", e)))
-            }
-            rdr.seek(pos)?;
-        }
+        let num_columns = parsed_content.columns.len();
+        let mut bad_rows: Option<Writer<File>> = None;
 
-        // this loop is for the lines in a file
-        for raw_record in rdr.records() {
+        // this loop is for the lines in a file, the footer rows and the rows that match the
+        // skip pattern aren't data and aren't counted
+        for raw_record in input.row_filter.rows(rdr.records(), parsed_content.dialect.delimiter) {
             parsed_content.records_parsed += 1;
             let record = match raw_record {
                 Ok(rec) => rec,
@@ -100,33 +308,97 @@ impl InputService for CSVService {
                     continue
                 }
             };
-            // this loop is for the columns
-            for (col_index, col_data) in record.clone().iter().enumerate() {
-                if parsed_content.columns[col_index].is_data_type_changeable() {
-                    let possible_type: DataTypes = CSVService::check_field_data_type(col_data);
-                    parsed_content.columns[col_index].potential_types.push(possible_type);
+
+            let record = match record.len() == num_columns {
+                true => record,
+                false => {
+                    parsed_content.ragged_rows += 1;
+                    match CSVService::fit_record(record.clone(), num_columns, input.ragged_rows, input.value_parser.null_value()) {
+                        Some(fitted) => fitted,
+                        None if input.ragged_rows == RaggedRows::SideFile => {
+                            if let Err(e) = CSVService::write_bad_row(&mut bad_rows, input, &parsed_content.dialect, header_row.as_ref(), &record) {
+                                parsed_content.errors.push(format!("{} -> bad rows error -> {}", &parsed_content.file_name, e));
+                            }
+                            continue
+                        },
+                        None => {
+                            // the reader's line numbers start after the skipped lines
+                            let line = record.position().map_or(0, |pos| pos.line()) + num_skipped;
+                            parsed_content.errors.push(format!("{} -> parse error -> the record on line {} has {} fields, the header has {}",
+                                                               &parsed_content.file_name, line, record.len(), num_columns));
+                            continue
+                        },
+                    }
+                },
+            };
+            if let Some(check) = key_check.as_mut() {
+                check.add(&record, &input.value_parser);
+            }
+            if let Some(values) = distinct_values.as_mut() {
+                values.add(&record, &input.value_parser);
+            }
+
+            // this loop is for the columns, each potential type is only recorded
+            // once so the memory used does not grow with the size of the file
+            for (col_index, col_data) in record.iter().enumerate() {
+                let possible_type: DataTypes = CSVService::check_field_data_type(col_data, &input.value_parser);
+                let column = &mut parsed_content.columns[col_index];
+                if column.is_data_type_changeable() && !column.potential_types.contains(&possible_type) {
+                    column.potential_types.push(possible_type);
+                }
+                // null values like NA aren't identifiers and don't count towards the
+                // lengths of the column's values
+                if possible_type != DataTypes::Empty {
+                    column.stats.add(col_data, possible_type, &input.value_parser);
+                    id_checks[col_index].add(col_data);
                 }
             }
-            parsed_content.content.push(record);
         }
 
+        CSVService::keep_identifiers_as_strings(&mut parsed_content.columns, &id_checks, &input.numeric_columns, input.fixed_width_ids);
+        if let Some(check) = key_check {
+            parsed_content.keys_unchecked = check.has_too_many_rows();
+            parsed_content.candidate_keys = check.candidate_keys().iter()
+                .map(|key| key.iter().map(|idx| parsed_content.columns[*idx].name.clone()).collect())
+                .collect();
+        }
+        if let Some(values) = distinct_values {
+            parsed_content.distinct_values = values.into_columns();
+        }
         parsed_content.set_column_data_types();
         Ok(parsed_content)
     }
+
+    // records re-reads the input with the dialect and header setting parse used and returns
+    // its records one at a time. Records that could not be parsed, or ragged rows that aren't
+    // loaded, were reported by parse and are skipped here, the rows parse dropped are dropped
+    // here too
+    fn records(&self, input: &InputSource, dialect: &Dialect, has_headers: bool) -> Result<Records, Error> {
+        let (mut rdr, _) = CSVService::create_reader(input, dialect, has_headers)?;
+        let num_columns = rdr.headers()?.len();
+        let ragged_rows = input.ragged_rows;
+        let padding = input.value_parser.null_value().to_string();
+
+        Ok(Box::new(input.row_filter.rows(rdr.into_records(), dialect.delimiter)
+            .filter_map(|rec| rec.ok())
+            .filter_map(move |rec| CSVService::fit_record(rec, num_columns, ragged_rows, &padding))))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs::File;
-    use crate::InputSource;
+    use crate::{Dialect, InputSource, RaggedRows, Trim};
+    use crate::parsers::rows::RowFilter;
+    use crate::parsers::values::{NullValues, NumberFormat};
 
     #[test]
     fn create_column_defs_with_valid_string_record() {
         let record = StringRecord::from(vec!["alpha", "bravo", "charlie"]);
         let svc = CSVService::default();
 
-        let col_defs = svc.create_column_defs(&record);
+        let col_defs = svc.create_column_defs(&record, true, &HeaderFormat::default());
         assert_eq!(3, col_defs.len());
         assert_eq!(String::from("alpha"), col_defs[0].name);
         assert_eq!(DataTypes::Empty, col_defs[0].data_type);
@@ -143,7 +415,7 @@ mod tests {
         let record = StringRecord::from(vec!["", "", ""]);
         let svc = CSVService::default();
 
-        let col_defs = svc.create_column_defs(&record);
+        let col_defs = svc.create_column_defs(&record, true, &HeaderFormat::default());
         assert_eq!(3, col_defs.len());
         assert_eq!(String::from("col_0"), col_defs[0].name);
         assert_eq!(DataTypes::Empty, col_defs[0].data_type);
@@ -157,22 +429,62 @@ mod tests {
 
     #[test]
     fn check_field_data_type_with_int() {
-        assert_eq!(CSVService::check_field_data_type("111"), DataTypes::I64);
+        assert_eq!(CSVService::check_field_data_type("111", &ValueParser::default()), DataTypes::I64);
     }
 
     #[test]
     fn check_field_data_type_with_float() {
-        assert_eq!(CSVService::check_field_data_type("11.1"), DataTypes::F64);
+        assert_eq!(CSVService::check_field_data_type("11.1", &ValueParser::default()), DataTypes::F64);
     }
 
     #[test]
     fn check_field_data_type_with_string() {
-        assert_eq!(CSVService::check_field_data_type("rob"), DataTypes::String);
+        assert_eq!(CSVService::check_field_data_type("rob", &ValueParser::default()), DataTypes::String);
+    }
+
+    #[test]
+    fn check_field_data_type_with_bool() {
+        assert_eq!(CSVService::check_field_data_type("true", &ValueParser::default()), DataTypes::Bool);
+        assert_eq!(CSVService::check_field_data_type("N", &ValueParser::default()), DataTypes::Bool);
+    }
+
+    #[test]
+    fn check_field_data_type_with_dates_and_times() {
+        assert_eq!(CSVService::check_field_data_type("2019-03-01", &ValueParser::default()), DataTypes::Date);
+        assert_eq!(CSVService::check_field_data_type("2019-03-01 08:15:00", &ValueParser::default()), DataTypes::DateTime);
+        assert_eq!(CSVService::check_field_data_type("2019-03-01T08:15:00Z", &ValueParser::default()), DataTypes::DateTimeTz);
+        assert_eq!(CSVService::check_field_data_type("08:15", &ValueParser::default()), DataTypes::Time);
+    }
+
+    #[test]
+    fn check_field_data_type_with_uuid_and_json() {
+        assert_eq!(CSVService::check_field_data_type("123e4567-e89b-12d3-a456-426655440000", &ValueParser::default()), DataTypes::Uuid);
+        assert_eq!(CSVService::check_field_data_type("{\"tags\": [\"a\"]}", &ValueParser::default()), DataTypes::Json);
+    }
+
+    #[test]
+    fn check_field_data_type_with_locale_numbers() {
+        let number_format = NumberFormat {
+            strip_currency: true,
+            strip_percent: true,
+            ..NumberFormat::for_locale("de_DE").unwrap()
+        };
+        let value_parser = ValueParser::new(Vec::new(), None, false, number_format, NullValues::default());
+        assert_eq!(CSVService::check_field_data_type("1.234", &value_parser), DataTypes::I64);
+        assert_eq!(CSVService::check_field_data_type("1.234,56 €", &value_parser), DataTypes::F64);
+        assert_eq!(CSVService::check_field_data_type("45%", &value_parser), DataTypes::I64);
+        assert_eq!(CSVService::check_field_data_type("1,234.56", &ValueParser::default()), DataTypes::String);
     }
 
     #[test]
     fn check_field_data_type_with_empty_string() {
-        assert_eq!(CSVService::check_field_data_type(""), DataTypes::Empty);
+        assert_eq!(CSVService::check_field_data_type("", &ValueParser::default()), DataTypes::Empty);
+    }
+
+    #[test]
+    fn check_field_data_type_with_null_values() {
+        assert_eq!(CSVService::check_field_data_type("NA", &ValueParser::default()), DataTypes::Empty);
+        assert_eq!(CSVService::check_field_data_type("\\N", &ValueParser::default()), DataTypes::Empty);
     }
 
     #[test]
@@ -190,17 +502,16 @@ mod tests {
         writeln!(tmp_file, "abc,def,ghi").unwrap();
         let tmp_path = tmp_dir.into_path();
 
-        let input_source = InputSource{
-            has_headers: true,
+        let input_source = InputSource {
             location: file_path.clone().into_os_string().into_string().unwrap(),
-            size: 0,
+            ..InputSource::default()
         };
 
         let svc = CSVService::default();
-        match svc.parse(input_source) {
+        match svc.parse(&input_source) {
             Ok(pc) => {
                 assert_eq!(pc.columns.len(),3);
-                assert_eq!(pc.content.len(), 1);
+                assert_eq!(pc.records_parsed, 1);
             },
             Err(e) => {
                 std::fs::remove_dir_all(file_path.clone());
@@ -223,14 +534,14 @@ mod tests {
         writeln!(tmp_file, "abc,def,ghi").unwrap();
         let tmp_path = tmp_dir.into_path();
 
-        let input_source = InputSource{
+        let input_source = InputSource {
             has_headers: false,
             location: file_path.clone().into_os_string().into_string().unwrap(),
-            size: 0,
+            ..InputSource::default()
         };
 
         let svc = CSVService::default();
-        match svc.parse(input_source) {
+        match svc.parse(&input_source) {
             Ok(pc) => {
                 assert_eq!(pc.columns.len(),3);
                 if pc.columns.len() == 3 {
@@ -238,7 +549,7 @@ mod tests {
                     assert_eq!(pc.columns[1].name, "col_1");
                     assert_eq!(pc.columns[2].name, "col_2");
                 }
-                assert_eq!(pc.content.len(), 1);
+                assert_eq!(pc.records_parsed, 1);
             },
             Err(e) => {
                 std::fs::remove_dir_all(file_path.clone());
@@ -246,4 +557,597 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn records_with_headers_streams_only_data_rows() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_records.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "first,second,third").unwrap();
+        writeln!(tmp_file, "abc,def,ghi").unwrap();
+        writeln!(tmp_file, "1,2,3").unwrap();
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let records: Vec<StringRecord> = svc.records(&input_source, &input_source.dialect, input_source.has_headers).unwrap().collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[0][0], "abc");
+        assert_eq!(&records[1][2], "3");
+    }
+
+    #[test]
+    fn parse_and_records_with_ragged_rows() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_ragged.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "id,name,total").unwrap();
+        writeln!(tmp_file, "1,rob,10").unwrap();
+        writeln!(tmp_file, "2,sam").unwrap();
+        writeln!(tmp_file, "3,kim,12,").unwrap();
+
+        let input = |ragged_rows| InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ragged_rows,
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        for (ragged_rows, num_errors, ids) in vec![(RaggedRows::Strict, 2, vec!["1"]),
+                                                     (RaggedRows::Pad, 1, vec!["1", "2"]),
+                                                     (RaggedRows::Truncate, 0, vec!["1", "2", "3"]),
+                                                     (RaggedRows::SideFile, 0, vec!["1"])] {
+            let pc = svc.parse(&input(ragged_rows)).unwrap();
+            assert_eq!((pc.ragged_rows, pc.errors.len()), (2, num_errors), "{:?}", ragged_rows);
+
+            let records: Vec<StringRecord> = svc.records(&input(ragged_rows), &pc.dialect, pc.has_headers).unwrap().collect();
+            assert_eq!(records.iter().map(|rec| &rec[0]).collect::<Vec<&str>>(), ids, "{:?}", ragged_rows);
+            assert!(records.iter().all(|rec| rec.len() == 3));
+        }
+
+        let bad_rows = std::fs::read_to_string(input(RaggedRows::SideFile).bad_rows_path()).unwrap();
+        assert_eq!(bad_rows, "id,name,total\n2,sam\n3,kim,12,\n");
+    }
+
+    #[test]
+    fn parse_and_records_skip_preamble_and_footer_rows() {
+        use std::io::Write;
+        use regex::Regex;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_statement.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "First Bank, Account Statement").unwrap();
+        writeln!(tmp_file, "\"Period: May 2019").unwrap();
+        writeln!(tmp_file, "date,amount").unwrap();
+        writeln!(tmp_file, "2019-05-01,10").unwrap();
+        writeln!(tmp_file, "Subtotal,10").unwrap();
+        writeln!(tmp_file, "2019-05-02,20").unwrap();
+        writeln!(tmp_file, "Total,30").unwrap();
+        writeln!(tmp_file, "Generated on 2019-06-01").unwrap();
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            row_filter: RowFilter {
+                skip_lines: 2,
+                skip_footer: 2,
+                skip_pattern: Some(Regex::new("^Subtotal,").unwrap()),
+                ..RowFilter::default()
+            },
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert!(pc.errors.is_empty());
+        assert_eq!(pc.records_parsed, 2);
+        assert_eq!(pc.columns[0].name, "date");
+        assert_eq!(pc.columns[0].data_type, DataTypes::Date);
+        assert_eq!(pc.columns[1].data_type, DataTypes::I64);
+
+        let records: Vec<StringRecord> = svc.records(&input_source, &pc.dialect, pc.has_headers).unwrap().collect();
+        assert_eq!(records.iter().map(|rec| &rec[1]).collect::<Vec<&str>>(), vec!["10", "20"]);
+    }
+
+    #[test]
+    fn parse_and_records_skip_the_rows_above_the_header_row() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_header_row.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        // the bank's address is quoted and spans two lines, it's the first of the two rows above the header
+        writeln!(tmp_file, "First Bank,\"100 Main St").unwrap();
+        writeln!(tmp_file, "Raleigh, NC\"").unwrap();
+        writeln!(tmp_file, "Period,May 2019").unwrap();
+        writeln!(tmp_file, "date,amount").unwrap();
+        writeln!(tmp_file, "2019-05-01,10").unwrap();
+        writeln!(tmp_file, "2019-05-02,20,extra").unwrap();
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            row_filter: RowFilter { skip_rows: 2, ..RowFilter::default() },
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns.iter().map(|col| col.name.as_str()).collect::<Vec<&str>>(), vec!["date", "amount"]);
+        assert_eq!(pc.errors, vec![format!("{} -> parse error -> the record on line 6 has 3 fields, the header has 2", pc.file_name)]);
+
+        let records: Vec<StringRecord> = svc.records(&input_source, &pc.dialect, pc.has_headers).unwrap().collect();
+        assert_eq!(records.iter().map(|rec| &rec[1]).collect::<Vec<&str>>(), vec!["10"]);
+    }
+
+    #[test]
+    fn parse_only_records_each_potential_type_once() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_potential_types.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "first").unwrap();
+        for n in 0..100 {
+            writeln!(tmp_file, "{}", n).unwrap();
+        }
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.records_parsed, 100);
+        assert_eq!(pc.columns[0].potential_types, vec![DataTypes::I64]);
+        assert_eq!(pc.columns[0].data_type, DataTypes::I64);
+    }
+
+    #[test]
+    fn parse_with_configured_date_formats() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_date_formats.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "ordered,shipped,delivered").unwrap();
+        writeln!(tmp_file, "03/01/2019,1551449700,2019-03-04 10:00").unwrap();
+        writeln!(tmp_file, "03/02/2019,1551536100,2019-03-05").unwrap();
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            value_parser: ValueParser::new(vec!["%m/%d/%Y".parse().unwrap(), "epoch".parse().unwrap()], None, false, NumberFormat::default(), NullValues::default()),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns[0].data_type, DataTypes::Date);
+        assert_eq!(pc.columns[1].data_type, DataTypes::DateTimeTz);
+        assert_eq!(pc.columns[2].data_type, DataTypes::DateTime);
+    }
+
+    #[test]
+    fn parse_collects_column_stats() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_stats.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "qty,price,name").unwrap();
+        writeln!(tmp_file, "7,1234.5,rob").unwrap();
+        writeln!(tmp_file, "-120,0.25,\"Zoë Smith\"").unwrap();
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!((pc.columns[0].stats.min_int, pc.columns[0].stats.max_int), (Some(-120), Some(7)));
+        assert_eq!((pc.columns[1].stats.int_digits, pc.columns[1].stats.scale), (4, 2));
+        assert_eq!(pc.columns[2].stats.max_length, 9);
+    }
+
+    #[test]
+    fn parse_normalizes_headers() {
+        use std::io::Write;
+        use crate::parsers::headers::HeaderCase;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_headers.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "Order ID,order_id,Größe (cm),id").unwrap();
+        writeln!(tmp_file, "1,2,3,4").unwrap();
+
+        let input_source = InputSource {
+            header_format: HeaderFormat { case: HeaderCase::Snake, max_length: Some(63) },
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        let names: Vec<&str> = pc.columns.iter().map(|col| col.name.as_str()).collect();
+        assert_eq!(names, vec!["order_id", "order_id_2", "grosse_cm", "id"]);
+        let headers: Vec<Option<&str>> = pc.columns.iter().map(|col| col.header.as_ref().map(|h| h.as_str())).collect();
+        assert_eq!(headers, vec![Some("Order ID"), Some("order_id"), Some("Größe (cm)"), None]);
+    }
+
+    #[test]
+    fn parse_proposes_candidate_keys() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_keys.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "year,month,code,total").unwrap();
+        writeln!(tmp_file, "2019,1,a,10").unwrap();
+        writeln!(tmp_file, "2019,2,b,10").unwrap();
+        writeln!(tmp_file, "2020,1,c,NA").unwrap();
+
+        let mut input_source = InputSource {
+            find_keys: true,
+            find_references: true,
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.candidate_keys, vec![vec![String::from("code")], vec![String::from("year"), String::from("month")]]);
+        let distinct: Vec<usize> = pc.distinct_values.iter().map(|values| values.as_ref().unwrap().len()).collect();
+        assert_eq!(distinct, vec![2, 2, 3, 1]);
+
+        // the keys aren't looked for when they aren't needed
+        input_source.find_keys = false;
+        input_source.find_references = false;
+        let pc = svc.parse(&input_source).unwrap();
+        assert!(pc.candidate_keys.is_empty());
+        assert!(pc.distinct_values.is_empty());
+    }
+
+    #[test]
+    fn parse_keeps_identifier_columns_as_strings() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_identifiers.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "zip,phone,account,qty").unwrap();
+        writeln!(tmp_file, "02134,5551234567,00042,7").unwrap();
+        writeln!(tmp_file, "90210,5559876543,00043,12").unwrap();
+
+        let mut input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns[0].data_type, DataTypes::String);
+        assert_eq!(pc.columns[0].type_reason, Some(String::from("values have leading zeros, e.g. '02134'")));
+        assert_eq!(pc.columns[1].data_type, DataTypes::I64);
+        assert_eq!(pc.columns[1].type_reason, None);
+        assert_eq!(pc.columns[3].data_type, DataTypes::I64);
+        assert_eq!(pc.columns[3].type_reason, None);
+
+        input_source.fixed_width_ids = true;
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns[1].data_type, DataTypes::String);
+        assert_eq!(pc.columns[1].type_reason, Some(String::from("values are all 10 digits long")));
+        assert_eq!(pc.columns[3].data_type, DataTypes::I64);
+
+        input_source.numeric_columns = vec![String::from("Account")];
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns[2].data_type, DataTypes::I64);
+        assert_eq!(pc.columns[2].type_reason, None);
+    }
+
+    #[test]
+    fn parse_with_pipe_delimiter_comments_and_trimming() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_dialect.psv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "# exported by the vendor").unwrap();
+        writeln!(tmp_file, "first | second").unwrap();
+        writeln!(tmp_file, "abc, def | 12").unwrap();
+
+        let mut dialect = Dialect::default();
+        dialect.comment = Some(b'#');
+        dialect.delimiter = b'|';
+        dialect.trim = Trim::All;
+
+        let input_source = InputSource {
+            dialect,
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns.len(), 2);
+        assert_eq!(pc.columns[0].name, "first");
+        assert_eq!(pc.columns[1].name, "second");
+        assert_eq!(pc.columns[1].data_type, DataTypes::I64);
+
+        let records: Vec<StringRecord> = svc.records(&input_source, &pc.dialect, pc.has_headers).unwrap().collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(&records[0][0], "abc, def");
+    }
+
+    #[test]
+    fn sniff_has_headers_with_numeric_columns() {
+        let sample = b"name,age,score\nrob,21,1.5\nbob,22,2.5\n";
+        assert_eq!(CSVService::sniff_has_headers(sample, &Dialect::default(), &ValueParser::default()), Some(true));
+    }
+
+    #[test]
+    fn sniff_has_headers_without_header_row() {
+        let sample = b"rob,21,1.5\nbob,22,2.5\n";
+        assert_eq!(CSVService::sniff_has_headers(sample, &Dialect::default(), &ValueParser::default()), Some(false));
+    }
+
+    #[test]
+    fn sniff_has_headers_with_only_strings_is_undecided() {
+        let sample = b"name,city\nrob,columbus\n";
+        assert_eq!(CSVService::sniff_has_headers(sample, &Dialect::default(), &ValueParser::default()), None);
+    }
+
+    #[test]
+    fn parse_with_sniffed_dialect() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_sniffing.tsv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "name\tamount").unwrap();
+        writeln!(tmp_file, "rob, jr\t12").unwrap();
+        writeln!(tmp_file, "bob\t13").unwrap();
+
+        let input_source = InputSource {
+            detect_headers: true,
+            has_headers: false,
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            sniff_dialect: true,
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.dialect.delimiter, b'\t');
+        assert_eq!(pc.has_headers, true);
+        assert_eq!(pc.columns[0].name, "name");
+        assert_eq!(pc.columns[1].data_type, DataTypes::I64);
+
+        let records: Vec<StringRecord> = svc.records(&input_source, &pc.dialect, pc.has_headers).unwrap().collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[0][0], "rob, jr");
+    }
+
+    #[test]
+    fn parse_with_cached_dialect() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_cached_dialect.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+
+        writeln!(tmp_file, "name;amount").unwrap();
+        writeln!(tmp_file, "rob;12").unwrap();
+        writeln!(tmp_file, "bob;13").unwrap();
+
+        // the cached dialect is used instead of the one sniffed from the file
+        let cached = Dialect { delimiter: b';', ..Dialect::default() };
+        let input_source = InputSource {
+            cached_dialect: Some((cached, false)),
+            detect_headers: true,
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            sniff_dialect: true,
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.dialect.delimiter, b';');
+        assert_eq!(pc.has_headers, false);
+        assert_eq!(pc.columns[0].name, "col_0");
+        assert_eq!(pc.records_parsed, 3);
+    }
+
+    #[test]
+    fn parse_detects_the_header_row_of_each_file() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let with_headers = tmp_dir.path().join("testing_detect_headers.csv");
+        let without_headers = tmp_dir.path().join("testing_detect_no_headers.csv");
+        let mut tmp_file = File::create(with_headers.clone()).unwrap();
+        writeln!(tmp_file, "name,age,joined").unwrap();
+        writeln!(tmp_file, "rob,21,2019-03-01").unwrap();
+        writeln!(tmp_file, "bob,22,2019-03-02").unwrap();
+        let mut tmp_file = File::create(without_headers.clone()).unwrap();
+        writeln!(tmp_file, "rob,21,2019-03-01").unwrap();
+        writeln!(tmp_file, "bob,22,2019-03-02").unwrap();
+
+        let input = |path: &std::path::PathBuf| InputSource {
+            detect_headers: true,
+            location: path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input(&with_headers)).unwrap();
+        assert_eq!(pc.has_headers, true);
+        assert_eq!(pc.columns[0].name, "name");
+        assert_eq!(pc.records_parsed, 2);
+
+        let pc = svc.parse(&input(&without_headers)).unwrap();
+        assert_eq!(pc.has_headers, false);
+        assert_eq!(pc.columns[0].name, "col_0");
+        assert_eq!(pc.columns[1].data_type, DataTypes::I64);
+        assert_eq!(pc.records_parsed, 2);
+
+        let records: Vec<StringRecord> = svc.records(&input(&without_headers), &pc.dialect, pc.has_headers).unwrap().collect();
+        assert_eq!(&records[0][0], "rob");
+
+        // a file whose header row isn't detected keeps its setting
+        let pc = svc.parse(&InputSource { detect_headers: false, ..input(&without_headers) }).unwrap();
+        assert_eq!(pc.has_headers, true);
+        assert_eq!(pc.columns[0].name, "rob");
+    }
+
+    #[test]
+    fn parse_with_latin1_file() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_latin1.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+        tmp_file.write_all(b"name,city\nJos\xE9,Z\xFCrich\n").unwrap();
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert!(pc.errors.is_empty());
+
+        let records: Vec<StringRecord> = svc.records(&input_source, &pc.dialect, pc.has_headers).unwrap().collect();
+        assert_eq!(&records[0][0], "José");
+        assert_eq!(&records[0][1], "Zürich");
+    }
+
+    #[test]
+    fn parse_with_utf16le_bom_file() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_utf16.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+        let mut content: Vec<u8> = vec![0xFF, 0xFE];
+        for unit in "name,age\nJosé,21\n".encode_utf16() {
+            content.push((unit & 0xFF) as u8);
+            content.push((unit >> 8) as u8);
+        }
+        tmp_file.write_all(&content).unwrap();
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns[0].name, "name");
+        assert_eq!(pc.columns[1].data_type, DataTypes::I64);
+
+        let records: Vec<StringRecord> = svc.records(&input_source, &pc.dialect, pc.has_headers).unwrap().collect();
+        assert_eq!(&records[0][0], "José");
+    }
+
+    #[test]
+    fn parse_strips_utf8_bom_from_first_column_name() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_utf8_bom.csv");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+        tmp_file.write_all(b"\xEF\xBB\xBFname,age\nrob,21\n").unwrap();
+
+        let input_source = InputSource {
+            encoding: Some(encoding_rs::UTF_8),
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns[0].name, "name");
+    }
+
+    #[test]
+    fn parse_with_gzip_compressed_file() {
+        use std::io::Write;
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("testing_compressed.csv.gz");
+        let tmp_file = File::create(file_path.clone()).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(tmp_file, flate2::Compression::default());
+        writeln!(encoder, "first,second").unwrap();
+        writeln!(encoder, "abc,12").unwrap();
+        encoder.finish().unwrap();
+
+        let input_source = InputSource {
+            location: file_path.clone().into_os_string().into_string().unwrap(),
+            ..InputSource::default()
+        };
+
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.columns[0].name, "first");
+        assert_eq!(pc.columns[1].data_type, DataTypes::I64);
+        assert_eq!(pc.records_parsed, 1);
+    }
+
+    #[test]
+    fn parse_and_records_with_piped_content() {
+        use std::io::Write;
+        use std::sync::Arc;
+        use crate::Origin;
+        use crate::parsers::stdin;
+
+        let piped_input = |content: Vec<u8>| InputSource {
+            location: String::from(stdin::PATH),
+            origin: Origin::Stdin(Arc::new(stdin::spool(&mut std::io::Cursor::new(content)).unwrap())),
+            ..InputSource::default()
+        };
+
+        let input_source = piped_input(b"id,name\n1,rob\n2,kim\n".to_vec());
+        assert_eq!(input_source.get_name(), "stdin");
+        assert_eq!(input_source.bad_rows_path(), "stdin.bad.csv");
+
+        // the spooled content is read once to parse it and again for its records
+        let svc = CSVService::default();
+        let pc = svc.parse(&input_source).unwrap();
+        assert_eq!(pc.file_name, "stdin");
+        assert_eq!(pc.columns[1].name, "name");
+        assert_eq!(pc.records_parsed, 2);
+
+        let records: Vec<StringRecord> = svc.records(&input_source, &pc.dialect, pc.has_headers).unwrap().collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[1][1], "kim");
+
+        // piped content is decompressed like a file
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        writeln!(encoder, "id,name").unwrap();
+        writeln!(encoder, "1,rob").unwrap();
+        let pc = svc.parse(&piped_input(encoder.finish().unwrap())).unwrap();
+        assert_eq!(pc.columns[0].data_type, DataTypes::I64);
+        assert_eq!(pc.records_parsed, 1);
+    }
 }
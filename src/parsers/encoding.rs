@@ -0,0 +1,130 @@
+//! Detects the character encoding of an input so it can be transcoded to UTF-8 before
+//! it is parsed
+use std::io::{self, BufRead, BufReader, Read};
+use std::str;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+/// the number of bytes from the beginning of an input used to detect its encoding
+pub const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// returns the encoding for the given label, e.g. utf-8, latin1 or utf-16le
+pub fn for_label(label: &str) -> Result<&'static Encoding, failure::Error> {
+    match Encoding::for_label(label.trim().as_bytes()) {
+        Some(encoding) => Ok(encoding),
+        None => Err(failure::err_msg(format!("'{}' is not a supported encoding", label))),
+    }
+}
+
+/// wraps the reader in one that transcodes its content to UTF-8. When no encoding is given
+/// it is detected from the beginning of the content, UTF-8 content is passed through as is.
+/// A BOM takes precedence over the encoding and is always stripped so it does not end up
+/// in the first column's name
+pub fn decode<R: Read + 'static>(reader: R, encoding: Option<&'static Encoding>) -> io::Result<Box<dyn Read>> {
+    let mut content = BufReader::with_capacity(SAMPLE_SIZE, reader);
+    let detected = match encoding {
+        Some(encoding) => encoding,
+        None => detect(content.fill_buf()?),
+    };
+
+    let mut builder = DecodeReaderBytesBuilder::new();
+    builder.bom_override(true).strip_bom(true);
+    if detected == UTF_8 && encoding.is_none() {
+        builder.utf8_passthru(true);
+    } else {
+        builder.encoding(Some(detected));
+    }
+
+    Ok(Box::new(builder.build(content)))
+}
+
+/// returns the encoding of the sample. A BOM is used when there is one, otherwise the
+/// pattern of NUL bytes is checked for UTF-16 without a BOM. Samples that are valid UTF-8
+/// are UTF-8, anything else is treated as Windows-1252, the superset of Latin-1 that Excel
+/// writes
+pub fn detect(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+
+    if let Some(encoding) = detect_utf16(sample) {
+        return encoding;
+    }
+
+    match str::from_utf8(sample) {
+        Ok(_) => UTF_8,
+        // the sample may have been cut off in the middle of a character
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
+
+// text that is mostly ASCII has a NUL byte in every other position when it is UTF-16
+// encoded, the NULs are the high bytes so their position gives the byte order
+fn detect_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+
+    let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+
+    if odd_nuls * 10 >= pairs * 4 && even_nuls * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_nuls * 10 >= pairs * 4 && odd_nuls * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_label_with_latin1() {
+        assert_eq!(for_label("latin1").unwrap(), WINDOWS_1252);
+    }
+
+    #[test]
+    fn for_label_with_unknown_label_returns_error() {
+        assert!(for_label("klingon").is_err());
+    }
+
+    #[test]
+    fn detect_with_utf8() {
+        assert_eq!(detect("name,city\nJosé,Zürich\n".as_bytes()), UTF_8);
+    }
+
+    #[test]
+    fn detect_with_utf8_bom() {
+        assert_eq!(detect(b"\xEF\xBB\xBFname,city\n"), UTF_8);
+    }
+
+    #[test]
+    fn detect_with_utf16le_bom() {
+        assert_eq!(detect(b"\xFF\xFEn\x00a\x00m\x00e\x00"), UTF_16LE);
+    }
+
+    #[test]
+    fn detect_with_utf16le_without_bom() {
+        assert_eq!(detect(b"n\x00a\x00m\x00e\x00,\x00c\x00i\x00t\x00y\x00\n\x00"), UTF_16LE);
+    }
+
+    #[test]
+    fn detect_with_utf16be_without_bom() {
+        assert_eq!(detect(b"\x00n\x00a\x00m\x00e\x00,\x00c\x00i\x00t\x00y\x00\n"), UTF_16BE);
+    }
+
+    #[test]
+    fn detect_with_latin1() {
+        assert_eq!(detect(b"name,city\nJos\xE9,Z\xFCrich\n"), WINDOWS_1252);
+    }
+
+    #[test]
+    fn detect_with_utf8_cut_off_mid_character() {
+        assert_eq!(detect(b"name,city\nJos\xC3"), UTF_8);
+    }
+}
@@ -0,0 +1,271 @@
+//! Turns the headers of an input into column names that can be used in any of the databases.
+//! Letters with accents are written without them, characters that can't be in a name become
+//! underscores, the names are put in the requested case, shortened to the database's limit and
+//! repeated names are given a number
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// The case the words of a column name are written in
+/// Keep: the words keep the case they have in the header
+/// Snake: the words are lowercase and separated by underscores, order_date
+/// Camel: the first word is lowercase and the others are capitalized, orderDate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeaderCase {
+    Keep,
+    Snake,
+    Camel,
+}
+
+impl Default for HeaderCase {
+    fn default() -> HeaderCase {
+        HeaderCase::Keep
+    }
+}
+
+impl FromStr for HeaderCase {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(HeaderCase::Keep),
+            "snake" => Ok(HeaderCase::Snake),
+            "camel" => Ok(HeaderCase::Camel),
+            _ => Err(failure::err_msg(format!("'{}' is not a header case, valid cases are keep, snake, and camel", s))),
+        }
+    }
+}
+
+/// How the headers are turned into column names
+/// case: the case of the words in the names
+/// max_length: the most characters a name can have in the database, 63 in Postgres and 64 in
+/// MySQL. None when the database has no limit
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HeaderFormat {
+    pub case: HeaderCase,
+    pub max_length: Option<usize>,
+}
+
+/// returns a column name for each header, no two names are the same when case is ignored.
+/// A name that is already taken is given the suffix _2, _3 and so on
+pub fn column_names<'a, I: IntoIterator<Item = &'a str>>(headers: I, format: &HeaderFormat) -> Vec<String> {
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut names: Vec<String> = Vec::new();
+
+    for (idx, header) in headers.into_iter().enumerate() {
+        let name = normalize(idx, header, format);
+        let mut unique_name = name.clone();
+        let mut count = 1;
+        while taken.contains(&unique_name.to_lowercase()) {
+            count += 1;
+            let suffix = format!("_{}", count);
+            unique_name = format!("{}{}", truncate(&name, format.max_length.map(|max| max - suffix.len())), suffix);
+        }
+
+        taken.insert(unique_name.to_lowercase());
+        names.push(unique_name);
+    }
+
+    names
+}
+
+/// returns the column name csv-to 0.5 made from the header. It only replaced +, -, . and /
+/// so tables created by it can have names, like firstminusname, that the headers now give
+/// different names to
+pub fn legacy_name(idx: usize, header: &str) -> String {
+    if header.is_empty() {
+        return format!("col_{}", idx);
+    }
+
+    let name = header.replace('+', "plus").replace('-', "minus").replace(&['.', '/'][..], "_");
+    match name.chars().next() {
+        Some(c) if c.is_numeric() => format!("_{}", name),
+        _ => name,
+    }
+}
+
+// turns a single header into a column name, an empty header is named after its position
+fn normalize(idx: usize, header: &str, format: &HeaderFormat) -> String {
+    // headers that are already names are left alone unless their case is changed
+    let is_name = header.chars().next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && header.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    let name = match (is_name, format.case) {
+        (true, HeaderCase::Keep) => header.to_string(),
+        (_, case) => {
+            let words = words(&ascii(header), case != HeaderCase::Keep);
+            match case {
+                HeaderCase::Keep if header.starts_with('_') => format!("_{}", words.join("_")),
+                HeaderCase::Keep => words.join("_"),
+                HeaderCase::Snake => words.iter().map(|w| w.to_lowercase()).collect::<Vec<String>>().join("_"),
+                HeaderCase::Camel => words.iter()
+                    .enumerate()
+                    .map(|(n, w)| match n {
+                        0 => w.to_lowercase(),
+                        _ => capitalize(w),
+                    })
+                    .collect(),
+            }
+        },
+    };
+
+    let name = match name.chars().next() {
+        None => format!("col_{}", idx),
+        Some(c) if c.is_ascii_digit() => format!("_{}", name),
+        Some(_) => name,
+    };
+    truncate(&name, format.max_length)
+}
+
+// writes the header with ASCII letters, digits and underscores. Accented letters lose their
+// accents, + and - on their own become plus and minus, a - between letters or digits
+// separates words and everything else becomes an underscore
+fn ascii(header: &str) -> String {
+    let chars: Vec<char> = header.chars().collect();
+    let mut name = String::new();
+
+    for (idx, c) in chars.iter().enumerate() {
+        let between_words = idx > 0
+            && idx + 1 < chars.len()
+            && chars[idx - 1].is_alphanumeric()
+            && chars[idx + 1].is_alphanumeric();
+
+        match c {
+            c if c.is_ascii_alphanumeric() => name.push(*c),
+            '-' if between_words => name.push('_'),
+            '+' => name.push_str("plus"),
+            '-' => name.push_str("minus"),
+            c if !c.is_ascii() => name.push_str(&transliterate(*c).unwrap_or_else(|| String::from("_"))),
+            _ => name.push('_'),
+        }
+    }
+
+    name
+}
+
+// returns the letter without its accent, uppercase letters stay uppercase
+fn transliterate(c: char) -> Option<String> {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    let plain = match lower {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'ł' | 'ľ' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'ß' => "ss",
+        'ś' | 'š' | 'ş' => "s",
+        'ť' | 'ţ' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    };
+
+    match c.is_uppercase() {
+        true => Some(capitalize(plain)),
+        false => Some(plain.to_string()),
+    }
+}
+
+// splits the name into words at underscores and, when the humps are split, where a lowercase
+// letter is followed by an uppercase letter. orderDate and order_date are both order and date
+fn words(name: &str, split_humps: bool) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut prev: Option<char> = None;
+
+    for c in name.chars() {
+        let hump = split_humps && c.is_ascii_uppercase() && prev.map_or(false, |p| p.is_ascii_lowercase());
+        if c == '_' || hump {
+            if !word.is_empty() {
+                words.push(word.clone());
+                word.clear();
+            }
+        }
+        if c != '_' {
+            word.push(c);
+        }
+        prev = Some(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+        None => String::new(),
+    }
+}
+
+fn truncate(name: &str, max_length: Option<usize>) -> String {
+    match max_length {
+        Some(max) => name.chars().take(max).collect(),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(headers: &[&str], case: HeaderCase, max_length: Option<usize>) -> Vec<String> {
+        column_names(headers.iter().cloned(), &HeaderFormat { case, max_length })
+    }
+
+    #[test]
+    fn column_names_keeps_names_and_cleans_the_rest() {
+        assert_eq!(names(&["id", "_code", "Order Date", "Total ($)", "+/-", "3B", "first-name", ""], HeaderCase::Keep, None),
+                   vec!["id", "_code", "Order_Date", "Total", "plus_minus", "_3B", "first_name", "col_7"]);
+    }
+
+    #[test]
+    fn column_names_with_snake_and_camel_case() {
+        let headers = ["Order Date", "customerID", "unitPrice", "ship_via"];
+        assert_eq!(names(&headers, HeaderCase::Snake, None), vec!["order_date", "customer_id", "unit_price", "ship_via"]);
+        assert_eq!(names(&headers, HeaderCase::Camel, None), vec!["orderDate", "customerId", "unitPrice", "shipVia"]);
+    }
+
+    #[test]
+    fn column_names_transliterates_accented_letters() {
+        assert_eq!(names(&["Größe", "Café", "Año", "日付"], HeaderCase::Keep, None), vec!["Grosse", "Cafe", "Ano", "col_3"]);
+    }
+
+    #[test]
+    fn column_names_gives_repeated_names_a_number() {
+        assert_eq!(names(&["Name", "name", "NAME", "name_2"], HeaderCase::Keep, None), vec!["Name", "name_2", "NAME_3", "name_2_2"]);
+    }
+
+    #[test]
+    fn column_names_truncates_to_the_max_length() {
+        let long = "a".repeat(70);
+        let truncated = names(&[&long, &long], HeaderCase::Keep, Some(63));
+        assert_eq!(truncated[0].len(), 63);
+        assert_eq!(truncated[1], format!("{}_2", "a".repeat(61)));
+    }
+
+    #[test]
+    fn legacy_name_only_replaced_a_few_characters() {
+        let headers = ["id", "Order Date", "+/-", "3B", "first-name", "unit.price"];
+        let legacy: Vec<String> = headers.iter().enumerate().map(|(idx, header)| legacy_name(idx, header)).collect();
+        assert_eq!(legacy, vec!["id", "Order Date", "plus_minus", "_3B", "firstminusname", "unit_price"]);
+        assert_eq!(legacy_name(4, ""), "col_4");
+    }
+
+    #[test]
+    fn header_case_from_str() {
+        assert_eq!(HeaderCase::from_str("Snake").unwrap(), HeaderCase::Snake);
+        assert!(HeaderCase::from_str("kebab").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! Detects columns of digits that are identifiers, like ZIP codes, account numbers and phone
+//! numbers, rather than quantities. Storing them as numbers would drop their leading zeros
+//! or round them so they are kept as strings
+
+// the fewest digits the values of a fixed width column must have for the column to be read as
+// identifiers, shorter fixed widths are too common in quantities, years for example
+const MIN_FIXED_WIDTH: usize = 5;
+
+// the fewest values a column must have before its fixed width is treated as a pattern
+const MIN_FIXED_WIDTH_VALUES: usize = 2;
+
+/// Keeps track of the digit only values in a column while it is parsed
+/// leading_zeros: the first value that starts with a zero
+/// out_of_range: the first value that is too large for a 64-bit integer
+/// width: the number of digits in the first value
+/// mixed_widths: a value had a different number of digits or wasn't only digits
+/// num_values: the number of digit only values
+#[derive(Clone, Debug, Default)]
+pub struct IdentifierCheck {
+    leading_zeros: Option<String>,
+    out_of_range: Option<String>,
+    width: Option<usize>,
+    mixed_widths: bool,
+    num_values: usize,
+}
+
+impl IdentifierCheck {
+    /// adds a value from the column, empty values are ignored and values with anything other
+    /// than digits end the fixed width check
+    pub fn add(&mut self, val: &str) {
+        if val.is_empty() {
+            return;
+        }
+
+        if !val.bytes().all(|b| b.is_ascii_digit()) {
+            self.mixed_widths = true;
+            return;
+        }
+
+        if self.leading_zeros.is_none() && val.len() > 1 && val.starts_with('0') {
+            self.leading_zeros = Some(val.to_string());
+        }
+
+        if self.out_of_range.is_none() && val.parse::<i64>().is_err() {
+            self.out_of_range = Some(val.to_string());
+        }
+
+        match self.width {
+            None => self.width = Some(val.len()),
+            Some(width) if width != val.len() => self.mixed_widths = true,
+            _ => (),
+        }
+        self.num_values += 1;
+    }
+
+    /// returns why the column holds identifiers or None if its values look like numbers. Values
+    /// that are all the same number of digits are only identifiers when fixed_width is true,
+    /// amounts and sequential ids are often all the same width too
+    pub fn reason(&self, fixed_width: bool) -> Option<String> {
+        if let Some(val) = &self.leading_zeros {
+            return Some(format!("values have leading zeros, e.g. '{}'", val));
+        }
+
+        if let Some(val) = &self.out_of_range {
+            return Some(format!("values are too large for a 64-bit integer, e.g. '{}'", val));
+        }
+
+        match self.width {
+            Some(width) if fixed_width && !self.mixed_widths && width >= MIN_FIXED_WIDTH && self.num_values >= MIN_FIXED_WIDTH_VALUES =>
+                Some(format!("values are all {} digits long", width)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(values: &[&str]) -> Option<String> {
+        check_fixed_width(values, false)
+    }
+
+    fn check_fixed_width(values: &[&str], fixed_width: bool) -> Option<String> {
+        let mut check = IdentifierCheck::default();
+        for val in values {
+            check.add(val);
+        }
+        check.reason(fixed_width)
+    }
+
+    #[test]
+    fn reason_with_leading_zeros() {
+        assert_eq!(check(&["1", "", "00123"]).unwrap(), "values have leading zeros, e.g. '00123'");
+    }
+
+    #[test]
+    fn reason_with_values_out_of_range() {
+        assert_eq!(check(&["12", "98765432109876543210"]).unwrap(), "values are too large for a 64-bit integer, e.g. '98765432109876543210'");
+    }
+
+    #[test]
+    fn reason_with_fixed_width_values() {
+        assert_eq!(check_fixed_width(&["5551234567", "5559876543"], true).unwrap(), "values are all 10 digits long");
+        assert_eq!(check(&["5551234567", "5559876543"]), None);
+    }
+
+    #[test]
+    fn reason_with_quantities_is_none() {
+        assert_eq!(check(&["0", "12", "345"]), None);
+        assert_eq!(check(&["2017", "2018", "2019"]), None);
+        assert_eq!(check(&["12345", "-12345"]), None);
+        assert_eq!(check(&["10000", "25000", "10001"]), None);
+        assert_eq!(check_fixed_width(&["12345"], true), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! Detects the columns, and pairs of columns, whose values are all different and never empty
+//! so they can be used as a table's primary key. The values are tracked by their hashes, a
+//! column stops being tracked as soon as one of its values repeats or is empty and the keys
+//! aren't checked when the input has too many records to track in memory. The distinct
+//! values of every column can be collected too so the columns that refer to another input's
+//! keys can be found
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use csv::StringRecord;
+
+use super::values::ValueParser;
+
+// the most hashes tracked for all of the columns and pairs together, about 70MB. Every column
+// or pair that is still tracked has a hash for each record, so the columns that repeat a value
+// early leave room for more records of the others. Once the budget is used up the keys aren't
+// checked so the memory used stays bounded
+const MAX_TRACKED_HASHES: usize = 4_000_000;
+
+// the most distinct values collected for a column, the values of columns with more aren't
+// compared to the keys of other inputs
+const MAX_DISTINCT_VALUES: usize = 100_000;
+
+// pairs of columns are only tracked for the first few columns, keys are usually at the start
+// of a record and the number of pairs grows with the square of the number of columns
+const MAX_PAIR_COLUMNS: usize = 6;
+
+// the fewest records an input must have before a key is proposed, every column of a single
+// record is unique
+const MIN_KEY_RECORDS: usize = 2;
+
+/// Keeps track of the distinct values of each column and pair of columns while an input
+/// is parsed. A column or pair is None once it can't be a key
+#[derive(Clone, Debug, Default)]
+pub struct KeyCheck {
+    columns: Vec<Option<HashSet<u64>>>,
+    pairs: Vec<((usize, usize), Option<HashSet<u64>>)>,
+    num_records: usize,
+    max_hashes: usize,
+    too_many_rows: bool,
+}
+
+impl KeyCheck {
+    /// creates a check for an input with the number of columns
+    pub fn new(num_columns: usize) -> KeyCheck {
+        let pair_columns = num_columns.min(MAX_PAIR_COLUMNS);
+        let mut pairs = Vec::new();
+        for first in 0..pair_columns {
+            for second in first + 1..pair_columns {
+                pairs.push(((first, second), Some(HashSet::new())));
+            }
+        }
+
+        KeyCheck {
+            columns: vec![Some(HashSet::new()); num_columns],
+            pairs,
+            num_records: 0,
+            max_hashes: MAX_TRACKED_HASHES,
+            too_many_rows: false,
+        }
+    }
+
+    /// adds a record's values, empty and null values end the tracking of their columns
+    pub fn add(&mut self, record: &StringRecord, value_parser: &ValueParser) {
+        if self.too_many_rows {
+            return;
+        }
+        self.num_records += 1;
+
+        let hashes: Vec<Option<u64>> = record.iter()
+            .map(|val| match value_parser.is_null(val) {
+                true => None,
+                false => Some(hash(&val)),
+            })
+            .collect();
+
+        for (idx, column) in self.columns.iter_mut().enumerate() {
+            let value = hashes.get(idx).cloned().unwrap_or(None);
+            KeyCheck::track(column, value);
+        }
+
+        for ((first, second), pair) in self.pairs.iter_mut() {
+            let value = match (hashes.get(*first), hashes.get(*second)) {
+                (Some(Some(a)), Some(Some(b))) => Some(hash(&(a, b))),
+                _ => None,
+            };
+            KeyCheck::track(pair, value);
+        }
+
+        let num_tracked = self.columns.iter().filter(|column| column.is_some()).count()
+            + self.pairs.iter().filter(|(_, pair)| pair.is_some()).count();
+        if self.num_records * num_tracked > self.max_hashes {
+            self.too_many_rows = true;
+            self.columns.iter_mut().for_each(|column| *column = None);
+            self.pairs.iter_mut().for_each(|(_, pair)| *pair = None);
+        }
+    }
+
+    /// returns true when the input had too many records for its keys to be checked
+    pub fn has_too_many_rows(&self) -> bool {
+        self.too_many_rows
+    }
+
+    /// returns the candidate keys, the indexes of the columns in each key. Single columns
+    /// come first, in column order, followed by the pairs that don't contain a single
+    /// column key
+    pub fn candidate_keys(&self) -> Vec<Vec<usize>> {
+        if self.num_records < MIN_KEY_RECORDS {
+            return Vec::new();
+        }
+
+        let singles: Vec<usize> = self.columns.iter()
+            .enumerate()
+            .filter(|(_, column)| column.is_some())
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let mut keys: Vec<Vec<usize>> = singles.iter().map(|idx| vec![*idx]).collect();
+        for ((first, second), pair) in &self.pairs {
+            if pair.is_some() && !singles.contains(first) && !singles.contains(second) {
+                keys.push(vec![*first, *second]);
+            }
+        }
+        keys
+    }
+
+    // adds the value's hash to the tracked hashes, tracking ends when the value is null or
+    // repeats
+    fn track(tracked: &mut Option<HashSet<u64>>, value: Option<u64>) {
+        let keep = match (tracked.as_mut(), value) {
+            (Some(hashes), Some(value)) => hashes.insert(value),
+            _ => false,
+        };
+
+        if !keep {
+            *tracked = None;
+        }
+    }
+}
+
+/// Collects the hashes of the distinct values in each column, empty and null values are
+/// skipped. A column is None once it has too many distinct values
+#[derive(Clone, Debug, Default)]
+pub struct DistinctValues {
+    columns: Vec<Option<HashSet<u64>>>,
+}
+
+impl DistinctValues {
+    /// creates the collection for an input with the number of columns
+    pub fn new(num_columns: usize) -> DistinctValues {
+        DistinctValues {
+            columns: vec![Some(HashSet::new()); num_columns],
+        }
+    }
+
+    /// adds a record's values
+    pub fn add(&mut self, record: &StringRecord, value_parser: &ValueParser) {
+        for (column, val) in self.columns.iter_mut().zip(record.iter()) {
+            if value_parser.is_null(val) {
+                continue;
+            }
+
+            let too_many = match column.as_mut() {
+                Some(hashes) => {
+                    hashes.insert(hash(&val));
+                    hashes.len() > MAX_DISTINCT_VALUES
+                },
+                None => false,
+            };
+            if too_many {
+                *column = None;
+            }
+        }
+    }
+
+    /// returns the hashes of each column's distinct values
+    pub fn into_columns(self) -> Vec<Option<HashSet<u64>>> {
+        self.columns
+    }
+}
+
+fn hash<T: Hash>(val: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    val.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(records: &[&[&str]]) -> Vec<Vec<usize>> {
+        let mut check = KeyCheck::new(records[0].len());
+        for record in records {
+            check.add(&StringRecord::from(record.to_vec()), &ValueParser::default());
+        }
+        check.candidate_keys()
+    }
+
+    #[test]
+    fn candidate_keys_with_single_columns() {
+        let keys = check(&[&["1", "rob", "a"], &["2", "rob", "b"], &["3", "sam", ""]]);
+        assert_eq!(keys, vec![vec![0]]);
+    }
+
+    #[test]
+    fn candidate_keys_with_a_pair_of_columns() {
+        let keys = check(&[&["2019", "1", "10"], &["2019", "2", "10"], &["2020", "1", "12"]]);
+        assert_eq!(keys, vec![vec![0, 1], vec![1, 2]]);
+    }
+
+    #[test]
+    fn candidate_keys_with_too_many_rows() {
+        // with 6 columns and their 15 pairs all unique the budget runs out after 100 records
+        let mut check = KeyCheck { max_hashes: 2_100, ..KeyCheck::new(6) };
+        for n in 0..100 {
+            let n = n.to_string();
+            check.add(&StringRecord::from(vec![n.as_str(); 6]), &ValueParser::default());
+        }
+        assert!(!check.has_too_many_rows());
+
+        check.add(&StringRecord::from(vec!["x"; 6]), &ValueParser::default());
+        assert!(check.has_too_many_rows());
+        assert!(check.candidate_keys().is_empty());
+    }
+
+    #[test]
+    fn candidate_keys_with_repeated_columns_leave_room_for_more_records() {
+        // only the first column and its 5 pairs are still tracked, with all 8 columns and 15
+        // pairs the budget would run out after 100 records
+        let mut check = KeyCheck { max_hashes: 2_300, ..KeyCheck::new(8) };
+        for n in 0..300 {
+            let n = n.to_string();
+            check.add(&StringRecord::from(vec![n.as_str(), "a", "b", "c", "d", "e", "f", "g"]), &ValueParser::default());
+        }
+        assert!(!check.has_too_many_rows());
+        assert_eq!(check.candidate_keys(), vec![vec![0]]);
+    }
+
+    #[test]
+    fn distinct_values_skips_null_values() {
+        let mut values = DistinctValues::new(2);
+        for record in &[["1", "a"], ["2", "NA"], ["1", "a"]] {
+            values.add(&StringRecord::from(record.to_vec()), &ValueParser::default());
+        }
+        let columns = values.into_columns();
+        assert_eq!(columns[0].as_ref().unwrap().len(), 2);
+        assert_eq!(columns[1].as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn candidate_keys_with_null_values_and_single_records() {
+        assert_eq!(check(&[&["1", "a"], &["NA", "b"], &["3", "b"]]), Vec::<Vec<usize>>::new());
+        assert_eq!(check(&[&["1", "a"]]), Vec::<Vec<usize>>::new());
+    }
+}
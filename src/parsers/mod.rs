@@ -1,41 +1,26 @@
 //! The parsers module
 //!
 //!
+pub mod archive;
+pub mod compression;
 pub mod csv;
+pub mod encoding;
+pub mod headers;
+pub mod identifiers;
+pub mod keys;
+pub mod rows;
+pub mod sniffer;
+pub mod stats;
+pub mod stdin;
+pub mod values;
 
 use failure::Error;
-use regex::Regex;
-use std::str;
-use crate::{InputSource, ParsedContent};
+use crate::{Dialect, InputSource, ParsedContent, Records};
 
 pub trait InputService {
     // responsible for parsing the file, creating a description of each column in the file
-    fn parse(&self, input: InputSource) -> Result<ParsedContent, Error> ;
-    // ensures that the name of the field is valid for creating columns in a database table and
-    // struct fields
-    fn validate_field_name(&self, idx: usize,  name: &str, name_re: &Regex) -> String {
-        if name == "" {
-            return format!("col_{}", idx);
-        }
-
-        let mut name_str = name.to_string();
-        if name_re.is_match(name) {
-            return name.to_string()
-        }
-
-        // 0. replace all + and - chars with plus and minus
-        name_str = str::replace(&name_str, "+", "plus");
-        name_str = str::replace(&name_str, "-", "minus");
-        name_str = str::replace( &name_str, ".", "_");
-
-        // 0.5 replace / with a _
-        name_str = str::replace(&name_str, "/", "_");
-        // 1. if name starts with a number then add _ at the beginning
-        let first_char = name_str.chars().next().unwrap();
-        if first_char.is_numeric() {
-            return format!("_{}", name_str);
-        }
-
-        name_str
-    }
+    fn parse(&self, input: &InputSource) -> Result<ParsedContent, Error> ;
+    // returns a stream of the input's records so they can be stored without reading the
+    // entire input into memory, the dialect and header setting are the ones parse found
+    fn records(&self, input: &InputSource, dialect: &Dialect, has_headers: bool) -> Result<Records, Error>;
  }
\ No newline at end of file
@@ -0,0 +1,201 @@
+//! Drops the lines and rows of an input that aren't part of its table, like the title lines
+//! above the header of a bank export or the "Total" and "Generated on" rows below the data
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read};
+use csv::StringRecord;
+use csv_core::ReadRecordResult;
+use regex::Regex;
+
+use crate::Dialect;
+
+/// The rows of an input that are dropped before it is parsed
+/// skip_lines: the number of lines before the header row, or before the first record when the
+/// input has no header. The lines are skipped before the content is read as CSV so they don't
+/// have to be valid CSV
+/// skip_rows: the number of rows after the skipped lines that come before the header row, or
+/// before the first record when the input has no header. The rows are read as CSV so a quoted
+/// value can span lines
+/// skip_footer: the number of rows dropped from the end of the input
+/// skip_pattern: rows whose fields, joined by the delimiter, match the pattern are dropped
+#[derive(Clone, Debug, Default)]
+pub struct RowFilter {
+    pub skip_lines: usize,
+    pub skip_rows: usize,
+    pub skip_footer: usize,
+    pub skip_pattern: Option<Regex>,
+}
+
+impl RowFilter {
+    /// returns the content after the skipped lines, a content with fewer lines is read as empty
+    pub fn skip_lines(&self, content: Box<dyn Read>) -> Result<Box<dyn Read>, io::Error> {
+        if self.skip_lines == 0 {
+            return Ok(content);
+        }
+
+        let mut content = BufReader::new(content);
+        let mut line: Vec<u8> = Vec::new();
+        for _ in 0..self.skip_lines {
+            line.clear();
+            if content.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+        }
+
+        Ok(Box::new(content))
+    }
+
+    /// returns the content after the skipped rows and the number of lines the rows took up, the
+    /// rows are read with the dialect. A content with fewer rows is read as empty
+    pub fn skip_rows(&self, content: Box<dyn Read>, dialect: &Dialect) -> Result<(Box<dyn Read>, u64), io::Error> {
+        if self.skip_rows == 0 {
+            return Ok((content, 0));
+        }
+
+        let mut rdr = csv_core::ReaderBuilder::new()
+            .comment(dialect.comment)
+            .delimiter(dialect.delimiter)
+            .double_quote(dialect.double_quote)
+            .escape(dialect.escape)
+            .quote(dialect.quote)
+            .build();
+        // the fields of the skipped rows aren't kept, the buffers are written over when they fill up
+        let mut fields = [0u8; 1024];
+        let mut ends = [0usize; 64];
+
+        let mut content = BufReader::new(content);
+        let mut num_rows = 0;
+        let mut num_lines = 0;
+        while num_rows < self.skip_rows {
+            let input = content.fill_buf()?;
+            let (result, num_read, _, _) = rdr.read_record(input, &mut fields, &mut ends);
+            num_lines += input[..num_read].iter().filter(|&&b| b == b'\n').count() as u64;
+            content.consume(num_read);
+
+            match result {
+                ReadRecordResult::Record => num_rows += 1,
+                ReadRecordResult::End => break,
+                _ => (),
+            }
+        }
+
+        Ok((Box::new(content), num_lines))
+    }
+
+    /// drops the footer rows and the rows that match the pattern from the records. Records that
+    /// couldn't be read are never matched, they are still reported as errors
+    pub fn rows<I>(&self, records: I, delimiter: u8) -> impl Iterator<Item = csv::Result<StringRecord>>
+        where I: Iterator<Item = csv::Result<StringRecord>>
+    {
+        let pattern = self.skip_pattern.clone();
+        let delimiter = (delimiter as char).to_string();
+
+        SkipLast::new(records, self.skip_footer).filter(move |record| match (&pattern, record) {
+            (Some(pattern), Ok(record)) => !pattern.is_match(&record.iter().collect::<Vec<&str>>().join(&delimiter)),
+            _ => true,
+        })
+    }
+}
+
+// hands back the items of an iterator except for its last count items, the items are held
+// back until count more items follow them
+struct SkipLast<I: Iterator> {
+    items: I,
+    held: VecDeque<I::Item>,
+    count: usize,
+}
+
+impl<I: Iterator> SkipLast<I> {
+    fn new(items: I, count: usize) -> SkipLast<I> {
+        SkipLast {
+            items,
+            held: VecDeque::with_capacity(count + 1),
+            count,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for SkipLast<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        while self.held.len() <= self.count {
+            self.held.push_back(self.items.next()?);
+        }
+        self.held.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(rows: &[&str]) -> Vec<csv::Result<StringRecord>> {
+        rows.iter().map(|row| Ok(StringRecord::from(row.split(',').collect::<Vec<&str>>()))).collect()
+    }
+
+    fn first_fields(filter: &RowFilter, rows: &[&str]) -> Vec<String> {
+        filter.rows(records(rows).into_iter(), b',')
+            .map(|rec| rec.unwrap()[0].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn skip_lines_drops_the_leading_lines() {
+        let filter = RowFilter { skip_lines: 2, ..RowFilter::default() };
+        let content: Box<dyn Read> = Box::new(&b"Account Statement\r\n\"Period: May, 2019\nid,total\n1,10\n"[..]);
+
+        let mut rest = String::new();
+        filter.skip_lines(content).unwrap().read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "id,total\n1,10\n");
+    }
+
+    #[test]
+    fn skip_lines_with_fewer_lines_than_skipped() {
+        let filter = RowFilter { skip_lines: 5, ..RowFilter::default() };
+        let content: Box<dyn Read> = Box::new(&b"id,total\n1,10"[..]);
+
+        let mut rest = String::new();
+        filter.skip_lines(content).unwrap().read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn skip_rows_reads_the_rows_as_csv() {
+        let filter = RowFilter { skip_rows: 2, ..RowFilter::default() };
+        let content: Box<dyn Read> = Box::new(&b"Account,\"First Bank\nRaleigh, NC\"\r\nPeriod,May\nid,total\n1,10\n"[..]);
+
+        let (content, num_lines) = filter.skip_rows(content, &Dialect::default()).unwrap();
+        let mut rest = String::new();
+        BufReader::new(content).read_to_string(&mut rest).unwrap();
+        assert_eq!((rest.trim_start(), num_lines), ("id,total\n1,10\n", 3));
+    }
+
+    #[test]
+    fn skip_rows_with_fewer_rows_than_skipped() {
+        let filter = RowFilter { skip_rows: 5, ..RowFilter::default() };
+        let content: Box<dyn Read> = Box::new(&b"id,total\n1,10"[..]);
+
+        let (content, _) = filter.skip_rows(content, &Dialect::default()).unwrap();
+        let mut rest = String::new();
+        BufReader::new(content).read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn rows_drops_the_footer_and_matching_rows() {
+        let rows = ["1,10", "2,20", "Subtotal,30", "3,5", "Total,35", "Generated on 2019-05-31,"];
+        assert_eq!(first_fields(&RowFilter::default(), &rows).len(), 6);
+
+        let footer = RowFilter { skip_footer: 2, ..RowFilter::default() };
+        assert_eq!(first_fields(&footer, &rows), vec!["1", "2", "Subtotal", "3"]);
+
+        let pattern = RowFilter { skip_pattern: Some(Regex::new("^(Sub)?[Tt]otal,|^Generated on").unwrap()), ..RowFilter::default() };
+        assert_eq!(first_fields(&pattern, &rows), vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn rows_with_a_footer_longer_than_the_input() {
+        let footer = RowFilter { skip_footer: 3, ..RowFilter::default() };
+        assert!(first_fields(&footer, &["1,10", "2,20"]).is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+//! Detects the dialect of an input from a sample of its leading bytes
+use std::collections::HashMap;
+use csv::ReaderBuilder;
+
+use crate::Dialect;
+
+/// the number of bytes read from the beginning of an input when sniffing its dialect
+pub const SAMPLE_SIZE: usize = 64 * 1024;
+
+// the delimiters that are tried, when two of them score the same the one that
+// appears first wins
+const DELIMITERS: [u8; 5] = [b',', b'\t', b'|', b';', b':'];
+const QUOTES: [u8; 2] = [b'"', b'\''];
+
+/// returns the dialect that best describes the sample. The options that cannot be
+/// detected, comments and trimming, are copied from the base dialect
+pub fn sniff_dialect(sample: &[u8], base: &Dialect) -> Dialect {
+    let mut dialect = base.clone();
+    dialect.quote = sniff_quote(sample, base.quote);
+    dialect.delimiter = sniff_delimiter(sample, &dialect);
+
+    // a quote preceded by a backslash that isn't the end of a field means the quotes
+    // are escaped rather than doubled
+    let escaped = sample.windows(3)
+        .filter(|w| w[0] == b'\\' && w[1] == dialect.quote && w[2] != dialect.delimiter && w[2] != b'\n')
+        .count();
+    if escaped > 0 {
+        dialect.double_quote = false;
+        dialect.escape = Some(b'\\');
+    }
+
+    dialect
+}
+
+// the quote character is the one that most often starts a field, if no field in the
+// sample is quoted the given default is used
+fn sniff_quote(sample: &[u8], default: u8) -> u8 {
+    let mut best = (default, 0);
+    for quote in QUOTES.iter() {
+        let mut count = 0;
+        let mut prev = b'\n';
+        for &b in sample {
+            if b == *quote && (prev == b'\n' || DELIMITERS.contains(&prev)) {
+                count += 1;
+            }
+            prev = b;
+        }
+
+        if count > best.1 {
+            best = (*quote, count);
+        }
+    }
+
+    best.0
+}
+
+// each candidate delimiter is used to parse the sample, the delimiter that splits the
+// most records into the same number of fields wins. If no candidate splits the records
+// into more than one field the dialect's delimiter is kept
+fn sniff_delimiter(sample: &[u8], dialect: &Dialect) -> u8 {
+    let mut best: Option<(f64, usize, u8)> = None;
+
+    for delimiter in DELIMITERS.iter() {
+        let mut rdr = ReaderBuilder::new()
+            .comment(dialect.comment)
+            .delimiter(*delimiter)
+            .flexible(true)
+            .has_headers(false)
+            .quote(dialect.quote)
+            .from_reader(sample);
+
+        let mut field_counts: HashMap<usize, usize> = HashMap::new();
+        let mut num_records = 0;
+        for rec in rdr.records() {
+            if let Ok(rec) = rec {
+                *field_counts.entry(rec.len()).or_insert(0) += 1;
+                num_records += 1;
+            }
+        }
+
+        let (num_fields, freq) = match field_counts.into_iter().max_by_key(|&(len, freq)| (freq, len)) {
+            Some(mode) => mode,
+            None => continue,
+        };
+        if num_fields < 2 {
+            continue;
+        }
+
+        let consistency = freq as f64 / num_records as f64;
+        let is_better = match best {
+            None => true,
+            Some((best_consistency, best_fields, _)) =>
+                consistency > best_consistency || (consistency == best_consistency && num_fields > best_fields),
+        };
+        if is_better {
+            best = Some((consistency, num_fields, *delimiter));
+        }
+    }
+
+    match best {
+        Some((_, _, delimiter)) => delimiter,
+        None => dialect.delimiter,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_dialect_with_commas() {
+        let sample = b"first,second,third\nabc,def,ghi\n1,2,3\n";
+        let dialect = sniff_dialect(sample, &Dialect::default());
+        assert_eq!(dialect.delimiter, b',');
+        assert_eq!(dialect.quote, b'"');
+    }
+
+    #[test]
+    fn sniff_dialect_with_tabs() {
+        let sample = b"first\tsecond\tthird\nabc, def\tghi\tjkl\n1\t2\t3\n";
+        let dialect = sniff_dialect(sample, &Dialect::default());
+        assert_eq!(dialect.delimiter, b'\t');
+    }
+
+    #[test]
+    fn sniff_dialect_with_pipes_and_single_quotes() {
+        let sample = b"'first'|'second'\n'a,b'|'c'\n'd'|'e,f'\n";
+        let dialect = sniff_dialect(sample, &Dialect::default());
+        assert_eq!(dialect.delimiter, b'|');
+        assert_eq!(dialect.quote, b'\'');
+    }
+
+    #[test]
+    fn sniff_dialect_with_backslash_escaped_quotes() {
+        let sample = b"first,second\n\"say \\\"hi\\\" now\",2\n";
+        let dialect = sniff_dialect(sample, &Dialect::default());
+        assert_eq!(dialect.escape, Some(b'\\'));
+        assert_eq!(dialect.double_quote, false);
+    }
+
+    #[test]
+    fn sniff_dialect_with_single_column_keeps_base_delimiter() {
+        let sample = b"first\nabc\ndef\n";
+        let mut base = Dialect::default();
+        base.delimiter = b';';
+        assert_eq!(sniff_dialect(sample, &base).delimiter, b';');
+    }
+}
@@ -0,0 +1,127 @@
+//! Keeps track of the range, digits and lengths of a column's values while it is parsed so the
+//! column's database type can be sized to its values, e.g. SMALLINT instead of INTEGER or
+//! VARCHAR(5) instead of TEXT
+use serde_derive::{Deserialize, Serialize};
+
+use crate::DataTypes;
+use super::values::ValueParser;
+
+/// The range, digits and lengths of the values in a column
+/// min_int: the smallest integer value
+/// max_int: the largest integer value
+/// int_digits: the most digits before the decimal point in a number
+/// scale: the most digits after the decimal point in a number
+/// inexact: a number was written with an exponent, or was infinite or not a number, so the
+/// column's numbers can't be stored as exact decimals
+/// max_length: the number of characters in the longest value
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ColumnStats {
+    pub min_int: Option<i64>,
+    pub max_int: Option<i64>,
+    pub int_digits: usize,
+    pub scale: usize,
+    pub inexact: bool,
+    pub max_length: usize,
+}
+
+impl ColumnStats {
+    /// adds a value from the column, the data type is the type the value was read as
+    pub fn add(&mut self, val: &str, data_type: DataTypes, value_parser: &ValueParser) {
+        self.max_length = self.max_length.max(val.chars().count());
+
+        if data_type != DataTypes::I64 && data_type != DataTypes::F64 {
+            return;
+        }
+
+        let number = match value_parser.parse_number(val) {
+            Some(number) => number,
+            None => return,
+        };
+
+        if let Ok(int) = number.parse::<i64>() {
+            self.min_int = Some(self.min_int.map_or(int, |min| min.min(int)));
+            self.max_int = Some(self.max_int.map_or(int, |max| max.max(int)));
+        }
+
+        let digits = number.trim_start_matches(|c| c == '-' || c == '+');
+        if !digits.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+            self.inexact = true;
+            return;
+        }
+
+        let (whole, fraction) = match digits.find('.') {
+            Some(idx) => (&digits[..idx], &digits[idx + 1..]),
+            None => (digits, ""),
+        };
+        let whole = whole.trim_start_matches('0');
+        self.int_digits = self.int_digits.max(whole.len().max(1));
+        self.scale = self.scale.max(fraction.len());
+    }
+
+    /// adds the stats of the same column in another input, the column's type is then sized to
+    /// the values of both
+    pub fn merge(&mut self, other: &ColumnStats) {
+        self.min_int = match (self.min_int, other.min_int) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max_int = match (self.max_int, other.max_int) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        self.int_digits = self.int_digits.max(other.int_digits);
+        self.scale = self.scale.max(other.scale);
+        self.inexact = self.inexact || other.inexact;
+        self.max_length = self.max_length.max(other.max_length);
+    }
+
+    /// returns the total number of digits the column's numbers need
+    pub fn precision(&self) -> usize {
+        self.int_digits + self.scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(values: &[(&str, DataTypes)]) -> ColumnStats {
+        let mut stats = ColumnStats::default();
+        for (val, data_type) in values {
+            stats.add(val, *data_type, &ValueParser::default());
+        }
+        stats
+    }
+
+    #[test]
+    fn add_with_integers() {
+        let s = stats(&[("12", DataTypes::I64), ("-40000", DataTypes::I64), ("", DataTypes::Empty)]);
+        assert_eq!((s.min_int, s.max_int), (Some(-40000), Some(12)));
+        assert_eq!((s.int_digits, s.scale, s.max_length), (5, 0, 6));
+    }
+
+    #[test]
+    fn add_with_decimals() {
+        let s = stats(&[("12.50", DataTypes::F64), ("-0.125", DataTypes::F64), ("1234", DataTypes::I64)]);
+        assert_eq!((s.int_digits, s.scale, s.precision()), (4, 3, 7));
+        assert!(!s.inexact);
+        assert!(stats(&[("1.5e10", DataTypes::F64)]).inexact);
+    }
+
+    #[test]
+    fn merge_keeps_the_widest_values() {
+        let mut s = stats(&[("12.5", DataTypes::F64), ("abc", DataTypes::String)]);
+        s.merge(&stats(&[("-40000", DataTypes::I64), ("1.125", DataTypes::F64), ("abcdefgh", DataTypes::String)]));
+        assert_eq!((s.min_int, s.max_int), (Some(-40000), Some(-40000)));
+        assert_eq!((s.int_digits, s.scale, s.max_length), (5, 3, 8));
+        assert!(!s.inexact);
+    }
+
+    #[test]
+    fn add_with_strings_only_counts_length() {
+        let s = stats(&[("Zoë", DataTypes::String), ("rob", DataTypes::String), ("12345", DataTypes::String)]);
+        assert_eq!(s.max_length, 5);
+        assert_eq!(s.min_int, None);
+        assert_eq!(s.int_digits, 0);
+    }
+}
@@ -0,0 +1,61 @@
+//! Reads CSV data piped to csv-to through stdin
+use std::fs;
+use std::io::{self, Read};
+use tempfile::NamedTempFile;
+
+/// the file path used on the command line to read from stdin
+pub const PATH: &str = "-";
+
+/// returns true if stdin is a pipe or a redirected file rather than a terminal
+/// or /dev/null, which is how csv-to is run by other programs without input
+#[cfg(unix)]
+pub fn is_piped() -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    match fs::metadata("/dev/stdin") {
+        Ok(meta) => meta.file_type().is_fifo() || meta.file_type().is_file(),
+        Err(_) => false,
+    }
+}
+
+/// stdin is only read when - is given as a file on platforms where a pipe
+/// can't be detected
+#[cfg(not(unix))]
+pub fn is_piped() -> bool {
+    false
+}
+
+/// copies all of the reader's content into a temporary file that is removed when
+/// it is dropped. Piped content can only be read once, the copy is read as many
+/// times as needed
+pub fn spool<R: Read>(reader: &mut R) -> io::Result<NamedTempFile> {
+    let mut spool = NamedTempFile::new()?;
+    io::copy(reader, &mut spool)?;
+    Ok(spool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spool_can_be_read_more_than_once() {
+        let mut piped = io::Cursor::new(b"id,name\n1,rob\n".to_vec());
+        let spool = spool(&mut piped).unwrap();
+
+        for _ in 0..2 {
+            let mut content = String::new();
+            spool.reopen().unwrap().read_to_string(&mut content).unwrap();
+            assert_eq!(content, "id,name\n1,rob\n");
+        }
+    }
+
+    #[test]
+    fn spool_with_empty_and_large_content() {
+        let spool_len = |content: Vec<u8>| spool(&mut io::Cursor::new(content)).unwrap().as_file().metadata().unwrap().len();
+        assert_eq!(spool_len(Vec::new()), 0);
+
+        // larger than the buffers used to copy it
+        assert_eq!(spool_len(b"1,rob\n".repeat(100_000)), 600_000);
+    }
+}
@@ -0,0 +1,662 @@
+//! Recognizes the numbers, booleans, dates, times, UUIDs and JSON documents in the fields of an input.
+//! The same functions are used to infer a column's data type and to convert its values when
+//! they are stored so both always agree on what a value is
+use std::borrow::Cow;
+use std::str::FromStr;
+use chrono::{DateTime, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::DataTypes;
+
+// the values that are read as true and false, compared without case
+const TRUE_VALUES: [&str; 4] = ["true", "t", "yes", "y"];
+const FALSE_VALUES: [&str; 4] = ["false", "f", "no", "n"];
+
+// the ISO-8601 formats that are always recognized, the date and time can be separated by a T
+// or a space and the seconds are optional
+const DEFAULT_FORMATS: [&str; 12] = [
+    "%Y-%m-%d",
+    "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M",
+    "rfc3339", "%Y-%m-%dT%H:%M:%S%.f%:z", "%Y-%m-%d %H:%M:%S%.f%:z", "%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z",
+    "%H:%M:%S%.f", "%H:%M",
+];
+
+// the values that are stored as NULL when no null values are given
+const DEFAULT_NULL_VALUES: [&str; 4] = ["NA", "NULL", "\\N", "-"];
+
+// the currency symbols that are removed from the start or end of a number
+const CURRENCY_SYMBOLS: [char; 12] = ['$', '€', '£', '¥', '₹', '₽', '₩', '₺', '₪', '฿', '₫', '¢'];
+
+// the characters read as a space when the thousands separator is a space, French and other
+// locales use a no-break space or a narrow no-break space
+const SPACE_SEPARATORS: [char; 3] = [' ', '\u{a0}', '\u{202f}'];
+
+// epoch seconds are only recognized in integers with this many digits, 1973 through 2286,
+// so small integers like counts and ids aren't read as dates in 1970
+const EPOCH_DIGITS: std::ops::RangeInclusive<usize> = 9..=10;
+
+/// A format that dates and times are read with
+/// Epoch: seconds since 1970-01-01 00:00:00 UTC
+/// Rfc2822: dates like Tue, 1 Jul 2003 10:52:37 +0200
+/// Rfc3339: dates like 2003-07-01T10:52:37+02:00
+/// Pattern: a strftime pattern like %m/%d/%Y and the data type of the values it reads
+#[derive(Clone, Debug, PartialEq)]
+pub enum DateFormat {
+    Epoch,
+    Rfc2822,
+    Rfc3339,
+    Pattern(String, DataTypes),
+}
+
+impl FromStr for DateFormat {
+    type Err = failure::Error;
+
+    // the data type of a pattern is determined by the date, time and timezone fields in it
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "epoch" => return Ok(DateFormat::Epoch),
+            "rfc2822" => return Ok(DateFormat::Rfc2822),
+            "rfc3339" | "iso8601" => return Ok(DateFormat::Rfc3339),
+            _ => (),
+        }
+
+        let (mut has_date, mut has_time, mut has_tz) = (false, false, false);
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+
+            // skips the padding flags and widths, %-d, %3f, %:z, etc.
+            let spec = chars.by_ref().find(|c| !"-_0.:#123456789".contains(*c));
+            match spec {
+                Some('Y') | Some('C') | Some('y') | Some('m') | Some('b') | Some('B') | Some('h') | Some('d') |
+                Some('e') | Some('j') | Some('D') | Some('x') | Some('F') | Some('v') => has_date = true,
+                Some('H') | Some('k') | Some('I') | Some('l') | Some('M') | Some('S') | Some('R') | Some('T') |
+                Some('X') | Some('r') => has_time = true,
+                Some('z') | Some('Z') => has_tz = true,
+                _ => (),
+            }
+        }
+
+        let data_type = match (has_date, has_time, has_tz) {
+            (true, true, true) => DataTypes::DateTimeTz,
+            (true, true, false) => DataTypes::DateTime,
+            (true, false, _) => DataTypes::Date,
+            (false, true, _) => DataTypes::Time,
+            (false, false, _) => return Err(failure::err_msg(format!("'{}' is not a supported date format, it has no date or time fields", s))),
+        };
+
+        Ok(DateFormat::Pattern(s.to_string(), data_type))
+    }
+}
+
+/// How the numbers in an input are written
+/// decimal: the character between the whole and fractional parts of a number
+/// thousands: the character between groups of three digits, numbers can't have one when None
+/// strip_currency: currency symbols before or after a number are removed, $12.00 is read as 12.00
+/// strip_percent: a percent sign after a number is removed, 45% is read as 45
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    pub decimal: char,
+    pub thousands: Option<char>,
+    pub strip_currency: bool,
+    pub strip_percent: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat {
+            decimal: '.',
+            thousands: None,
+            strip_currency: false,
+            strip_percent: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// returns the separators used by a locale like en_US, de-DE or fr. The country is only
+    /// needed for the countries that write numbers differently than the rest of the language
+    pub fn for_locale(locale: &str) -> Result<NumberFormat, failure::Error> {
+        let locale = locale.to_lowercase().replace('-', "_");
+        let language = locale.split('_').next().unwrap_or_default();
+
+        let (decimal, thousands) = match (language, locale.as_str()) {
+            (_, "de_ch") | (_, "fr_ch") | (_, "it_ch") | (_, "rm_ch") => ('.', Some('\'')),
+            (_, "es_mx") | (_, "es_us") => ('.', Some(',')),
+            ("c", _) | ("posix", _) => ('.', None),
+            ("en", _) | ("ja", _) | ("zh", _) | ("ko", _) | ("he", _) | ("hi", _) | ("th", _) | ("ms", _) => ('.', Some(',')),
+            ("de", _) | ("es", _) | ("it", _) | ("nl", _) | ("pt", _) | ("da", _) | ("id", _) | ("tr", _) |
+            ("el", _) | ("ro", _) | ("hr", _) | ("sl", _) | ("sr", _) => (',', Some('.')),
+            ("fr", _) | ("ru", _) | ("pl", _) | ("cs", _) | ("sk", _) | ("sv", _) | ("fi", _) | ("nb", _) |
+            ("nn", _) | ("no", _) | ("uk", _) | ("hu", _) | ("bg", _) | ("lt", _) | ("lv", _) | ("et", _) => (',', Some(' ')),
+            _ => return Err(failure::err_msg(format!("'{}' is not a supported locale", locale))),
+        };
+
+        Ok(NumberFormat {
+            decimal,
+            thousands,
+            ..NumberFormat::default()
+        })
+    }
+
+    // returns true if the character separates the thousands in a number
+    fn is_thousands(&self, c: char) -> bool {
+        match self.thousands {
+            Some(' ') => SPACE_SEPARATORS.contains(&c),
+            Some(sep) => c == sep,
+            None => false,
+        }
+    }
+
+    // returns the value without the currency symbol and percent sign the format allows
+    // and any sign in front of them
+    fn strip<'a>(&self, val: &'a str) -> (bool, &'a str) {
+        let mut val = val.trim();
+        let mut negative = false;
+        if let Some(rest) = val.strip_prefix('-') {
+            negative = true;
+            val = rest.trim_start();
+        }
+
+        if self.strip_currency {
+            val = val.trim_start_matches(&CURRENCY_SYMBOLS[..]).trim_start();
+            val = val.trim_end_matches(&CURRENCY_SYMBOLS[..]).trim_end();
+        }
+
+        if self.strip_percent {
+            if let Some(rest) = val.strip_suffix('%') {
+                val = rest.trim_end();
+            }
+        }
+
+        // the sign can follow the currency symbol too, $-12.00
+        if let Some(rest) = val.strip_prefix('-') {
+            negative = !negative;
+            val = rest;
+        }
+
+        (negative, val)
+    }
+}
+
+/// The values that are stored as NULL
+/// values: the sentinel values, like NA or \N, that are NULL in every column
+/// empty_strings: empty values in string columns are NULL instead of empty strings, empty
+/// values in the other columns are always NULL
+#[derive(Clone, Debug, PartialEq)]
+pub struct NullValues {
+    pub values: Vec<String>,
+    pub empty_strings: bool,
+}
+
+impl Default for NullValues {
+    fn default() -> NullValues {
+        NullValues {
+            values: DEFAULT_NULL_VALUES.iter().map(|v| v.to_string()).collect(),
+            empty_strings: false,
+        }
+    }
+}
+
+// a date or time read from a value before it's converted to the type of its column
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Parsed {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    DateTimeTz(DateTime<FixedOffset>),
+    Time(NaiveTime),
+}
+
+/// Reads the numbers, dates and times in an input's values
+/// date_formats: the formats tried, in order, the ISO-8601 formats are always tried last
+/// timezone: the timezone of the dates and times that don't have one, they stay without a
+/// timezone when None
+/// to_utc: dates and times with a timezone are converted to UTC before they are stored
+/// number_format: the separators, currency symbols and percent signs numbers are written with
+/// null_values: the values that are stored as NULL
+#[derive(Clone, Debug)]
+pub struct ValueParser {
+    date_formats: Vec<DateFormat>,
+    timezone: Option<Tz>,
+    to_utc: bool,
+    number_format: NumberFormat,
+    null_values: NullValues,
+}
+
+impl Default for ValueParser {
+    fn default() -> ValueParser {
+        ValueParser::new(Vec::new(), None, false, NumberFormat::default(), NullValues::default())
+    }
+}
+
+impl ValueParser {
+    /// creates a parser that tries the given formats before the ISO-8601 formats
+    pub fn new(date_formats: Vec<DateFormat>, timezone: Option<Tz>, to_utc: bool, number_format: NumberFormat,
+               null_values: NullValues) -> ValueParser {
+        let mut all_formats = date_formats;
+        for fmt in DEFAULT_FORMATS.iter() {
+            let default_format = fmt.parse::<DateFormat>().unwrap();
+            if !all_formats.contains(&default_format) {
+                all_formats.push(default_format);
+            }
+        }
+
+        ValueParser {
+            date_formats: all_formats,
+            timezone,
+            to_utc,
+            number_format,
+            null_values,
+        }
+    }
+
+    /// returns true if the value is empty or one of the null values, these values don't
+    /// count towards a column's data type
+    pub fn is_null(&self, val: &str) -> bool {
+        val.is_empty() || self.null_values.values.iter().any(|null_value| null_value == val)
+    }
+
+    /// returns a value that is stored as NULL in a column of any data type, it is empty when
+    /// there are no null values and empty strings are treated as NULL or kept
+    pub fn null_value(&self) -> &str {
+        self.null_values.values.first().map_or("", |val| val.as_str())
+    }
+
+    /// returns true if the value is stored as NULL in a column of the data type. Empty
+    /// values in string columns are only NULL when empty strings are treated as NULL
+    pub fn stores_null(&self, data_type: DataTypes, val: &str) -> bool {
+        match data_type {
+            DataTypes::String if val.is_empty() => self.null_values.empty_strings,
+            _ => self.is_null(val),
+        }
+    }
+
+    /// returns the number in the value written the way Rust and SQL read numbers, without
+    /// thousands separators, currency symbols or percent signs and with a . before the
+    /// fractional part. None is returned when the value isn't a number in the number format
+    pub fn parse_number<'a>(&self, val: &'a str) -> Option<Cow<'a, str>> {
+        let fmt = &self.number_format;
+        if fmt.decimal == '.' && val.parse::<f64>().is_ok() {
+            return Some(Cow::Borrowed(val));
+        }
+
+        let (negative, num) = fmt.strip(val);
+        let (whole, fraction) = match num.find(fmt.decimal) {
+            Some(idx) => (&num[..idx], Some(&num[idx + fmt.decimal.len_utf8()..])),
+            None => (num, None),
+        };
+
+        // the first group can be one to three digits long, the rest are always three
+        let groups: Vec<&str> = whole.split(|c: char| fmt.is_thousands(c)).collect();
+        let valid_groups = groups.iter().enumerate().all(|(idx, group)| {
+            let valid_len = match (idx, groups.len()) {
+                (_, 1) => true,
+                (0, _) => !group.is_empty() && group.len() <= 3,
+                _ => group.len() == 3,
+            };
+            valid_len && group.bytes().all(|b| b.is_ascii_digit())
+        });
+        let valid_fraction = fraction.map_or(true, |f| f.bytes().all(|b| b.is_ascii_digit()));
+        if !valid_groups || !valid_fraction || (whole.is_empty() && fraction.map_or(true, str::is_empty)) {
+            return None;
+        }
+
+        let mut number = String::with_capacity(num.len() + 1);
+        if negative {
+            number.push('-');
+        }
+        groups.iter().for_each(|group| number.push_str(group));
+        if let Some(fraction) = fraction {
+            number.push('.');
+            number.push_str(fraction);
+        }
+        Some(Cow::Owned(number))
+    }
+
+    /// returns the date or time data type of the value or None if it isn't a date or time.
+    /// Dates and times without a timezone are DateTimeTz when there is a default timezone
+    pub fn date_type(&self, val: &str) -> Option<DataTypes> {
+        match self.parse(val)? {
+            Parsed::Date(_) => Some(DataTypes::Date),
+            Parsed::DateTime(_) if self.timezone.is_none() => Some(DataTypes::DateTime),
+            Parsed::DateTime(_) | Parsed::DateTimeTz(_) => Some(DataTypes::DateTimeTz),
+            Parsed::Time(_) => Some(DataTypes::Time),
+        }
+    }
+
+    /// returns the date in the value
+    pub fn parse_date(&self, val: &str) -> Option<NaiveDate> {
+        match self.parse(val)? {
+            Parsed::Date(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// returns the date and time in a value without a timezone, dates are read as midnight
+    pub fn parse_datetime(&self, val: &str) -> Option<NaiveDateTime> {
+        match self.parse(val)? {
+            Parsed::Date(d) => d.and_hms_opt(0, 0, 0),
+            Parsed::DateTime(dt) => Some(dt),
+            _ => None,
+        }
+    }
+
+    /// returns the date and time in the value with its timezone. Values without a timezone
+    /// are in the default timezone, or UTC when there isn't one. When the parser converts to
+    /// UTC the returned date and time is in UTC
+    pub fn parse_datetime_tz(&self, val: &str) -> Option<DateTime<FixedOffset>> {
+        let dt = match self.parse(val)? {
+            Parsed::DateTimeTz(dt) => dt,
+            Parsed::Date(d) => self.localize(d.and_hms_opt(0, 0, 0)?)?,
+            Parsed::DateTime(dt) => self.localize(dt)?,
+            Parsed::Time(_) => return None,
+        };
+
+        match self.to_utc {
+            true => Some(dt.with_timezone(&Utc.fix())),
+            false => Some(dt),
+        }
+    }
+
+    /// returns the time in the value
+    pub fn parse_time(&self, val: &str) -> Option<NaiveTime> {
+        match self.parse(val)? {
+            Parsed::Time(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// returns the value in the ISO-8601 form dates and times of the data type are stored in,
+    /// None is returned if the value isn't a date or time of that type
+    pub fn format_value(&self, data_type: DataTypes, val: &str) -> Option<String> {
+        match data_type {
+            DataTypes::Date => self.parse_date(val).map(|d| d.format("%Y-%m-%d").to_string()),
+            DataTypes::DateTime => self.parse_datetime(val).map(|dt| dt.format("%Y-%m-%d %H:%M:%S%.f").to_string()),
+            DataTypes::DateTimeTz => self.parse_datetime_tz(val).map(|dt| dt.format("%Y-%m-%d %H:%M:%S%.f%:z").to_string()),
+            DataTypes::Time => self.parse_time(val).map(|t| t.format("%H:%M:%S%.f").to_string()),
+            _ => None,
+        }
+    }
+
+    // tries each of the formats and returns the value read by the first one that matches
+    fn parse(&self, val: &str) -> Option<Parsed> {
+        if !val.bytes().any(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        self.date_formats.iter().filter_map(|fmt| ValueParser::parse_with(fmt, val)).next()
+    }
+
+    fn parse_with(fmt: &DateFormat, val: &str) -> Option<Parsed> {
+        match fmt {
+            DateFormat::Epoch => {
+                if !EPOCH_DIGITS.contains(&val.len()) || !val.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                match Utc.timestamp_opt(val.parse::<i64>().ok()?, 0) {
+                    LocalResult::Single(dt) => Some(Parsed::DateTimeTz(dt.with_timezone(&Utc.fix()))),
+                    _ => None,
+                }
+            },
+            DateFormat::Rfc2822 => DateTime::parse_from_rfc2822(val).ok().map(Parsed::DateTimeTz),
+            DateFormat::Rfc3339 => DateTime::parse_from_rfc3339(val).ok().map(Parsed::DateTimeTz),
+            DateFormat::Pattern(pattern, DataTypes::Date) => NaiveDate::parse_from_str(val, pattern).ok().map(Parsed::Date),
+            DateFormat::Pattern(pattern, DataTypes::DateTime) => NaiveDateTime::parse_from_str(val, pattern).ok().map(Parsed::DateTime),
+            DateFormat::Pattern(pattern, DataTypes::DateTimeTz) => {
+                // %z doesn't read the Z that's used for UTC
+                let val = match val.ends_with('Z') {
+                    true => format!("{}+00:00", &val[..val.len() - 1]),
+                    false => val.to_string(),
+                };
+                DateTime::parse_from_str(&val, pattern).ok().map(Parsed::DateTimeTz)
+            },
+            DateFormat::Pattern(pattern, _) => NaiveTime::parse_from_str(val, pattern).ok().map(Parsed::Time),
+        }
+    }
+
+    // puts a date and time without a timezone in the default timezone, when the clocks go
+    // back the earlier of the two times is used. A time skipped when the clocks go forward is
+    // read with the offset from before they changed, which moves it forward by the length of
+    // the gap, 02:30 becomes 03:30
+    fn localize(&self, dt: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        let tz = match self.timezone {
+            Some(tz) => tz,
+            None => return Some(Utc.fix().from_utc_datetime(&dt)),
+        };
+
+        let local = match tz.from_local_datetime(&dt) {
+            LocalResult::Single(local) | LocalResult::Ambiguous(local, _) => local,
+            LocalResult::None => {
+                let before = (1..=24).filter_map(|hours| tz.from_local_datetime(&(dt - Duration::hours(hours))).earliest()).next()?;
+                let offset = before.offset().fix().local_minus_utc();
+                tz.from_utc_datetime(&(dt - Duration::seconds(i64::from(offset))))
+            },
+        };
+        Some(local.with_timezone(&local.offset().fix()))
+    }
+}
+
+/// returns the boolean the value represents, true/false, t/f, yes/no and y/n are recognized
+pub fn parse_bool(val: &str) -> Option<bool> {
+    let lower_val = val.to_lowercase();
+    if TRUE_VALUES.contains(&lower_val.as_str()) {
+        Some(true)
+    } else if FALSE_VALUES.contains(&lower_val.as_str()) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// returns true if the value is a UUID in its hyphenated form
+pub fn is_uuid(val: &str) -> bool {
+    val.len() == 36 && val.char_indices().all(|(idx, c)| match idx {
+        8 | 13 | 18 | 23 => c == '-',
+        _ => c.is_ascii_hexdigit(),
+    })
+}
+
+/// returns true if the value is a JSON object or array, other JSON values like
+/// numbers and strings are left to the other data types
+pub fn is_json(val: &str) -> bool {
+    let trimmed = val.trim();
+    (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser(formats: &[&str], timezone: Option<&str>, to_utc: bool) -> ValueParser {
+        ValueParser::new(formats.iter().map(|f| f.parse().unwrap()).collect(),
+                         timezone.map(|tz| tz.parse().unwrap()),
+                         to_utc,
+                         NumberFormat::default(),
+                         NullValues::default())
+    }
+
+    fn number_parser(locale: &str, strip_currency: bool, strip_percent: bool) -> ValueParser {
+        let number_format = NumberFormat {
+            strip_currency,
+            strip_percent,
+            ..NumberFormat::for_locale(locale).unwrap()
+        };
+        ValueParser::new(Vec::new(), None, false, number_format, NullValues::default())
+    }
+
+    #[test]
+    fn date_format_from_str_with_patterns() {
+        assert_eq!("%m/%d/%Y".parse::<DateFormat>().unwrap(), DateFormat::Pattern(String::from("%m/%d/%Y"), DataTypes::Date));
+        assert_eq!("%d.%m.%Y %H:%M".parse::<DateFormat>().unwrap(), DateFormat::Pattern(String::from("%d.%m.%Y %H:%M"), DataTypes::DateTime));
+        assert_eq!("%Y%m%d%H%M%S%:z".parse::<DateFormat>().unwrap(), DateFormat::Pattern(String::from("%Y%m%d%H%M%S%:z"), DataTypes::DateTimeTz));
+        assert_eq!("%-I:%M %p".parse::<DateFormat>().unwrap(), DateFormat::Pattern(String::from("%-I:%M %p"), DataTypes::Time));
+        assert_eq!("RFC2822".parse::<DateFormat>().unwrap(), DateFormat::Rfc2822);
+        assert!("day %%".parse::<DateFormat>().is_err());
+    }
+
+    #[test]
+    fn date_type_with_default_formats() {
+        let p = ValueParser::default();
+        assert_eq!(p.date_type("2019-02-28"), Some(DataTypes::Date));
+        assert_eq!(p.date_type("2019-02-28T13:45:10"), Some(DataTypes::DateTime));
+        assert_eq!(p.date_type("2019-02-28 13:45:10.123"), Some(DataTypes::DateTime));
+        assert_eq!(p.date_type("2019-02-28 13:45"), Some(DataTypes::DateTime));
+        assert_eq!(p.date_type("2019-02-28T13:45:10-05:00"), Some(DataTypes::DateTimeTz));
+        assert_eq!(p.date_type("2019-02-28 13:45:10Z"), Some(DataTypes::DateTimeTz));
+        assert_eq!(p.date_type("2019-02-28 13:45:10+0100"), Some(DataTypes::DateTimeTz));
+        assert_eq!(p.date_type("13:45"), Some(DataTypes::Time));
+        assert_eq!(p.date_type("2019-02-30"), None);
+        assert_eq!(p.date_type("02/28/2019"), None);
+        assert_eq!(p.date_type("1551361510"), None);
+    }
+
+    #[test]
+    fn date_type_with_configured_formats() {
+        let p = parser(&["%m/%d/%Y", "rfc2822", "epoch"], None, false);
+        assert_eq!(p.date_type("02/28/2019"), Some(DataTypes::Date));
+        assert_eq!(p.date_type("Thu, 28 Feb 2019 13:45:10 +0000"), Some(DataTypes::DateTimeTz));
+        assert_eq!(p.date_type("1551361510"), Some(DataTypes::DateTimeTz));
+        assert_eq!(p.date_type("42"), None);
+    }
+
+    #[test]
+    fn date_type_with_default_timezone_makes_datetimes_aware() {
+        let p = parser(&[], Some("America/New_York"), false);
+        assert_eq!(p.date_type("2019-02-28 13:45:10"), Some(DataTypes::DateTimeTz));
+        assert_eq!(p.date_type("2019-02-28"), Some(DataTypes::Date));
+    }
+
+    #[test]
+    fn format_value_with_default_timezone() {
+        let p = parser(&[], Some("America/New_York"), false);
+        assert_eq!(p.format_value(DataTypes::DateTimeTz, "2019-07-04 12:00:00").unwrap(), "2019-07-04 12:00:00-04:00");
+    }
+
+    #[test]
+    fn format_value_moves_times_in_a_gap_forward() {
+        let p = parser(&[], Some("America/New_York"), false);
+        assert_eq!(p.format_value(DataTypes::DateTimeTz, "2019-03-10 02:30:00").unwrap(), "2019-03-10 03:30:00-04:00");
+        assert_eq!(p.format_value(DataTypes::DateTimeTz, "2019-03-10 01:30:00").unwrap(), "2019-03-10 01:30:00-05:00");
+        assert_eq!(p.format_value(DataTypes::DateTimeTz, "2019-11-03 01:30:00").unwrap(), "2019-11-03 01:30:00-04:00");
+    }
+
+    #[test]
+    fn format_value_converts_to_utc() {
+        let p = parser(&["%m/%d/%Y %H:%M", "epoch"], Some("Europe/Berlin"), true);
+        assert_eq!(p.format_value(DataTypes::DateTimeTz, "01/15/2019 08:30").unwrap(), "2019-01-15 07:30:00+00:00");
+        assert_eq!(p.format_value(DataTypes::DateTimeTz, "2019-01-15T08:30:00-05:00").unwrap(), "2019-01-15 13:30:00+00:00");
+        assert_eq!(p.format_value(DataTypes::DateTimeTz, "1551361510").unwrap(), "2019-02-28 13:45:10+00:00");
+    }
+
+    #[test]
+    fn format_value_with_dates_and_times() {
+        let p = parser(&["%m/%d/%Y"], None, false);
+        assert_eq!(p.format_value(DataTypes::Date, "02/28/2019").unwrap(), "2019-02-28");
+        assert_eq!(p.format_value(DataTypes::DateTime, "02/28/2019").unwrap(), "2019-02-28 00:00:00");
+        assert_eq!(p.format_value(DataTypes::Time, "13:45").unwrap(), "13:45:00");
+        assert_eq!(p.format_value(DataTypes::Date, "yesterday"), None);
+    }
+
+    #[test]
+    fn number_format_for_locale() {
+        assert_eq!(NumberFormat::for_locale("en_US").unwrap().thousands, Some(','));
+        assert_eq!(NumberFormat::for_locale("de-DE").unwrap().decimal, ',');
+        assert_eq!(NumberFormat::for_locale("de_CH").unwrap().thousands, Some('\''));
+        assert_eq!(NumberFormat::for_locale("fr").unwrap().thousands, Some(' '));
+        assert!(NumberFormat::for_locale("xx").is_err());
+    }
+
+    #[test]
+    fn parse_number_with_default_format() {
+        let p = ValueParser::default();
+        assert_eq!(p.parse_number("-12.5").unwrap(), "-12.5");
+        assert_eq!(p.parse_number("1,234.56"), None);
+        assert_eq!(p.parse_number("$12.00"), None);
+    }
+
+    #[test]
+    fn parse_number_with_locales() {
+        let p = number_parser("en_US", false, false);
+        assert_eq!(p.parse_number("1,234.56").unwrap(), "1234.56");
+        assert_eq!(p.parse_number("-1,234,567").unwrap(), "-1234567");
+        assert_eq!(p.parse_number("1,23"), None);
+        assert_eq!(p.parse_number("1,2345"), None);
+
+        let p = number_parser("de_DE", false, false);
+        assert_eq!(p.parse_number("1.234,56").unwrap(), "1234.56");
+        assert_eq!(p.parse_number("0,5").unwrap(), "0.5");
+        assert_eq!(p.parse_number("1.234").unwrap(), "1234");
+        assert_eq!(p.parse_number("1.5"), None);
+
+        let p = number_parser("fr_FR", false, false);
+        assert_eq!(p.parse_number("1\u{202f}234,5").unwrap(), "1234.5");
+        assert_eq!(p.parse_number("1 234 567").unwrap(), "1234567");
+    }
+
+    #[test]
+    fn parse_number_strips_currency_and_percent() {
+        let p = number_parser("en", true, true);
+        assert_eq!(p.parse_number("$12.00").unwrap(), "12.00");
+        assert_eq!(p.parse_number("-$1,200").unwrap(), "-1200");
+        assert_eq!(p.parse_number("$-3.50").unwrap(), "-3.50");
+        assert_eq!(p.parse_number("45%").unwrap(), "45");
+        assert_eq!(p.parse_number("$"), None);
+
+        let p = number_parser("de", true, false);
+        assert_eq!(p.parse_number("1.234,56 €").unwrap(), "1234.56");
+        assert_eq!(p.parse_number("45%"), None);
+    }
+
+    #[test]
+    fn is_null_with_default_null_values() {
+        let p = ValueParser::default();
+        assert!(p.is_null(""));
+        assert!(p.is_null("NA"));
+        assert!(p.is_null("\\N"));
+        assert!(p.is_null("-"));
+        assert!(!p.is_null("na"));
+        assert!(!p.is_null("0"));
+    }
+
+    #[test]
+    fn stores_null_with_empty_strings() {
+        let p = ValueParser::default();
+        assert!(p.stores_null(DataTypes::I64, ""));
+        assert!(p.stores_null(DataTypes::String, "NULL"));
+        assert!(!p.stores_null(DataTypes::String, ""));
+
+        let null_values = NullValues {
+            values: vec![String::from("n/a")],
+            empty_strings: true,
+        };
+        let p = ValueParser::new(Vec::new(), None, false, NumberFormat::default(), null_values);
+        assert!(p.stores_null(DataTypes::String, ""));
+        assert!(p.stores_null(DataTypes::Date, "n/a"));
+        assert!(!p.stores_null(DataTypes::String, "NA"));
+    }
+
+    #[test]
+    fn parse_bool_with_supported_values() {
+        assert_eq!(parse_bool("TRUE"), Some(true));
+        assert_eq!(parse_bool("y"), Some(true));
+        assert_eq!(parse_bool("No"), Some(false));
+        assert_eq!(parse_bool("1"), None);
+    }
+
+    #[test]
+    fn is_uuid_with_hyphenated_uuid() {
+        assert!(is_uuid("123e4567-e89b-12d3-a456-426655440000"));
+        assert!(!is_uuid("123e4567e89b12d3a456426655440000"));
+    }
+
+    #[test]
+    fn is_json_with_objects_and_arrays() {
+        assert!(is_json("{\"a\": [1, 2]}"));
+        assert!(is_json("[1, 2]"));
+        assert!(!is_json("{not json}"));
+        assert!(!is_json("\"a string\""));
+    }
+}
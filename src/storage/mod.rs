@@ -1,26 +1,310 @@
 //! StorageService Ports and Adapters
 //!
 //! This module contains the StorageService trait and adapters for the supported data stores. Currently
-//! only Postgres and SQLite are supported.
+//! Postgres, MySQL and SQLite are supported.
 //!
 //!
 pub mod mysql;
 pub mod postgres;
 pub mod sqlite;
 
-use failure::Error;
 use csv::StringRecord;
-use crate::ColumnDef;
+use failure::Error;
+use crate::{ColumnDef, Records};
+use crate::parsers::values::ValueParser;
+
+/// the number of records inserted with each statement when --batch-size isn't given
+pub const BATCH_SIZE: usize = 1000;
 
 pub trait StorageService {
-    /// creates an insert or appropriate create statement for the backend store
-    fn create_insert_stmt(&self, store_name: String, column_defs: Vec<ColumnDef>) -> String;
+    /// starts a transaction, the statements that follow it are committed or rolled back together
+    fn begin_transaction(&self) -> Result<(), Error>;
+    /// commits the statements run since the transaction was started
+    fn commit_transaction(&self) -> Result<(), Error>;
+    /// undoes the statements run since the transaction was started
+    fn rollback_transaction(&self) -> Result<(), Error>;
+    /// creates an insert or appropriate create statement for the backend store that inserts the
+    /// number of rows, the values are bound to a placeholder for each column of each row so
+    /// they are never written into the statement
+    fn create_insert_stmt(&self, store_name: String, column_defs: Vec<ColumnDef>, num_rows: usize) -> String;
     /// describes a method that will create a table for relational databases or the equivalent in a
     /// store that is supported
     fn create_store(&self, name: String, column_defs: Vec<ColumnDef>, drop_tables: bool) -> Result<(), Error>;
+    /// drops the table with the given name if it exists
+    fn drop_store(&self, name: String) -> Result<(), Error>;
     /// deletes all data in the given table
     fn delete_data_in_table(&self, name: String) -> Result<(), Error>;
     /// stores the data in the store that implements this trait, a table in relational databases but
-    /// returns the number of records stored successfully or any error(s) the method encounters
-    fn store_data(&self, column_defs: Vec<ColumnDef>, data: Vec<StringRecord>, insert_stmt: String) -> Result<usize, Error>;
-}
\ No newline at end of file
+    /// returns the number of records stored successfully or any error(s) the method encounters.
+    /// The records are read from the input as they are needed and inserted batch_size at a time,
+    /// or fewer when the backend can't take that many in one statement. Their dates and times
+    /// are read with the same value parser that was used to determine the column data types.
+    /// When commit_every is given the open transaction is committed, and another one started,
+    /// after every commit_every records
+    fn store_data(&self, store_name: String, column_defs: Vec<ColumnDef>, data: Records, batch_size: usize, commit_every: Option<usize>, value_parser: &ValueParser) -> Result<StoredData, Error>;
+    /// stores the data with the store's bulk loader, COPY FROM STDIN for Postgres and LOAD DATA
+    /// LOCAL INFILE for MySQL, which is much faster than inserts. The data is loaded with a
+    /// single statement so nothing is stored when it returns an error, the records can then be
+    /// stored with store_data to find the ones that fail. Stores without a bulk loader return
+    /// an error
+    fn bulk_load_data(&self, store_name: String, column_defs: Vec<ColumnDef>, data: Records, value_parser: &ValueParser) -> Result<StoredData, Error>;
+}
+
+/// The records store_data or bulk_load_data stored
+/// num_stored: the number of records that were stored
+/// num_failed: the number of records the store was given but couldn't store
+/// num_committed: the number of the stored records that store_data committed, the others are
+/// committed or rolled back with the caller's transaction
+/// failed_batches: why each batch that couldn't be inserted failed, the records of a failed
+/// batch are inserted one at a time so only the records that can't be stored are lost
+/// failed_records: why each record that couldn't be inserted on its own failed, with its number
+/// warnings: the warnings the store gave while loading, like the rows it skipped or the
+/// values it had to truncate
+#[derive(Debug, Default, PartialEq)]
+pub struct StoredData {
+    pub num_stored: usize,
+    pub num_failed: usize,
+    pub num_committed: usize,
+    pub failed_batches: Vec<String>,
+    pub failed_records: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+// the limits a backend puts on a single insert statement
+// max_params: the most values that can be bound to the statement
+// max_bytes: the most bytes of values the statement can be sent with, None when the limit is
+// too large to matter
+struct BatchLimits {
+    max_params: usize,
+    max_bytes: Option<usize>,
+}
+
+impl BatchLimits {
+    // returns the most rows of the columns a batch can have, a batch always has at least a row
+    fn max_rows(&self, batch_size: usize, num_columns: usize) -> usize {
+        let max_rows = (self.max_params / num_columns.max(1)).max(1);
+        batch_size.max(1).min(max_rows)
+    }
+}
+
+// splits the records into batches of up to max_rows records whose values fit in max_bytes. A
+// record larger than max_bytes is a batch of its own
+fn batches(data: Records, max_rows: usize, max_bytes: Option<usize>) -> impl Iterator<Item = Vec<StringRecord>> {
+    let mut data = data.peekable();
+
+    std::iter::from_fn(move || {
+        let mut batch: Vec<StringRecord> = Vec::with_capacity(max_rows);
+        let mut bytes = 0;
+        while batch.len() < max_rows {
+            let size = match data.peek() {
+                Some(record) => record.as_byte_record().as_slice().len(),
+                None => break,
+            };
+            if !batch.is_empty() && max_bytes.map_or(false, |max| bytes + size > max) {
+                break;
+            }
+            bytes += size;
+            batch.extend(data.next());
+        }
+
+        match batch.is_empty() {
+            true => None,
+            false => Some(batch),
+        }
+    })
+}
+
+// returns the batch size with at most the records inserted between commits
+fn capped_batch_size(batch_size: usize, commit_every: Option<usize>) -> usize {
+    commit_every.map_or(batch_size, |every| batch_size.min(every))
+}
+
+// inserts the records in batches, insert stores the records it's given with a single statement.
+// When a batch fails its records are inserted one at a time and the failure is kept with the
+// numbers of the batch's first and last records, the records that still fail are reported
+// on their own. With commit_every, commit is called after the batch that brings the records
+// inserted since the last commit to commit_every, batches are never larger than commit_every
+fn store_batches<F, G>(data: Records, batch_size: usize, num_columns: usize, limits: &BatchLimits,
+                       commit_every: Option<usize>, mut insert: F, mut commit: G) -> Result<StoredData, Error>
+    where F: FnMut(&[StringRecord]) -> Result<(), Error>,
+          G: FnMut() -> Result<(), Error>
+{
+    let mut stored = StoredData::default();
+    let mut first = 1;
+    let mut since_commit = 0;
+    let max_rows = limits.max_rows(capped_batch_size(batch_size, commit_every), num_columns);
+
+    for batch in batches(data, max_rows, limits.max_bytes) {
+        let last = first + batch.len() - 1;
+        match insert(&batch) {
+            Ok(()) => stored.num_stored += batch.len(),
+            Err(e) if batch.len() == 1 => {
+                stored.num_failed += 1;
+                stored.failed_records.push(format!("record {} -> insert error -> {}", first, e));
+            },
+            Err(e) => {
+                let mut num_stored = 0;
+                for (idx, record) in batch.chunks(1).enumerate() {
+                    match insert(record) {
+                        Ok(()) => num_stored += 1,
+                        Err(e) => {
+                            stored.num_failed += 1;
+                            stored.failed_records.push(format!("record {} -> insert error -> {}", first + idx, e));
+                        },
+                    }
+                }
+                stored.num_stored += num_stored;
+                stored.failed_batches.push(format!("records {}-{} -> batch insert error -> {} -> {} of the {} records were inserted one at a time",
+                                                   first, last, e, num_stored, batch.len()));
+            },
+        }
+        first = last + 1;
+
+        since_commit += batch.len();
+        if let Some(every) = commit_every {
+            if since_commit >= every {
+                commit()?;
+                stored.num_committed = stored.num_stored;
+                since_commit = 0;
+            }
+        }
+    }
+
+    Ok(stored)
+}
+
+// returns the PRIMARY KEY and FOREIGN KEY constraints of a table with the columns, barrel only
+// creates primary keys for the id column it adds to tables and has no foreign keys. The
+// identifier function writes a table or column name the way the store needs it
+fn table_constraints<F: Fn(&str) -> String>(cols: &[ColumnDef], identifier: F) -> Vec<String> {
+    let mut constraints: Vec<String> = Vec::new();
+
+    let key: Vec<String> = cols.iter()
+        .filter(|cd| cd.primary_key)
+        .map(|cd| identifier(&cd.name))
+        .collect();
+    if !key.is_empty() {
+        constraints.push(format!("PRIMARY KEY ({})", key.join(", ")));
+    }
+
+    for cd in cols {
+        if let Some(reference) = &cd.references {
+            constraints.push(format!("FOREIGN KEY ({}) REFERENCES {} ({})",
+                                     identifier(&cd.name), identifier(&reference.table), identifier(&reference.column)));
+        }
+    }
+    constraints
+}
+
+// adds the table constraints to a CREATE TABLE statement made by barrel
+fn add_constraints(create_stmt: String, constraints: &[String]) -> String {
+    if constraints.is_empty() {
+        return create_stmt;
+    }
+
+    match create_stmt.rfind(')') {
+        Some(idx) => format!("{}, {}{}", &create_stmt[..idx], constraints.join(", "), &create_stmt[idx..]),
+        None => create_stmt,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataTypes, Reference};
+
+    fn column(name: &str, primary_key: bool, references: Option<Reference>) -> ColumnDef {
+        ColumnDef {
+            name: String::from(name),
+            data_type: DataTypes::I64,
+            primary_key,
+            references,
+            ..ColumnDef::default()
+        }
+    }
+
+    fn records(rows: &[&str]) -> Records {
+        let rows: Vec<StringRecord> = rows.iter().map(|row| StringRecord::from(row.split(',').collect::<Vec<&str>>())).collect();
+        Box::new(rows.into_iter())
+    }
+
+    #[test]
+    fn max_rows_with_parameter_limit() {
+        let limits = BatchLimits { max_params: 999, max_bytes: None };
+        assert_eq!(limits.max_rows(1000, 3), 333);
+        assert_eq!(limits.max_rows(100, 3), 100);
+        assert_eq!(limits.max_rows(0, 3), 1);
+        assert_eq!(limits.max_rows(1000, 2000), 1);
+    }
+
+    #[test]
+    fn batches_by_rows_and_bytes() {
+        let sizes = |max_rows, max_bytes| batches(records(&["1,a", "2,b", "3,c", "4,dddd", "5,e"]), max_rows, max_bytes)
+            .map(|batch| batch.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(sizes(2, None), vec![2, 2, 1]);
+        assert_eq!(sizes(10, Some(6)), vec![3, 1, 1]);
+        assert_eq!(sizes(10, Some(1)), vec![1, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn store_batches_inserts_the_records_of_a_failed_batch_one_at_a_time() {
+        let limits = BatchLimits { max_params: 100, max_bytes: None };
+        let mut statements = Vec::new();
+        let stored = store_batches(records(&["1,a", "2,b", "x,c", "4,d", "5,e"]), 2, 2, &limits, None, |batch| {
+            statements.push(batch.len());
+            match batch.iter().any(|record| &record[0] == "x") {
+                true => Err(failure::err_msg("invalid integer")),
+                false => Ok(()),
+            }
+        }, || Ok(())).unwrap();
+
+        assert_eq!((stored.num_stored, stored.num_failed), (4, 1));
+        assert_eq!(stored.failed_batches, vec!["records 3-4 -> batch insert error -> invalid integer -> 1 of the 2 records were inserted one at a time"]);
+        assert_eq!(stored.failed_records, vec!["record 3 -> insert error -> invalid integer"]);
+        assert_eq!(statements, vec![2, 2, 1, 1, 1]);
+    }
+
+    #[test]
+    fn store_batches_commits_every_n_records() {
+        let limits = BatchLimits { max_params: 100, max_bytes: None };
+        let mut statements = Vec::new();
+        let mut commits = 0;
+        let stored = store_batches(records(&["1,a", "2,b", "3,c", "4,d", "5,e"]), 10, 2, &limits, Some(2), |batch| {
+            statements.push(batch.len());
+            Ok(())
+        }, || {
+            commits += 1;
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(statements, vec![2, 2, 1]);
+        assert_eq!(commits, 2);
+        assert_eq!(stored.num_stored, 5);
+        assert_eq!(stored.num_committed, 4);
+    }
+
+    #[test]
+    fn table_constraints_with_keys() {
+        let reference = Reference { table: String::from("Customers"), column: String::from("id") };
+        let cols = vec![column("a", true, None), column("b", true, None), column("customer_id", false, Some(reference))];
+        assert_eq!(table_constraints(&cols, |name| format!("\"{}\"", name.to_lowercase())), vec![
+            "PRIMARY KEY (\"a\", \"b\")",
+            "FOREIGN KEY (\"customer_id\") REFERENCES \"customers\" (\"id\")",
+        ]);
+        assert!(table_constraints(&[column("a", false, None)], |name| name.to_string()).is_empty());
+    }
+
+    #[test]
+    fn add_constraints_with_composite_key() {
+        let stmt = String::from("CREATE TABLE t (a INTEGER, b NUMERIC(6,2));");
+        let constraints = vec![String::from("PRIMARY KEY (a, b)")];
+        assert_eq!(add_constraints(stmt, &constraints), "CREATE TABLE t (a INTEGER, b NUMERIC(6,2), PRIMARY KEY (a, b));");
+    }
+
+    #[test]
+    fn add_constraints_without_constraints() {
+        let stmt = String::from("CREATE TABLE t (a INTEGER);");
+        assert_eq!(add_constraints(stmt.clone(), &[]), stmt);
+    }
+}
@@ -1,42 +1,129 @@
+use std::cell::{Cell, RefCell};
 use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use barrel::backend::MySql;
 use barrel::*;
-use csv::StringRecord;
+use barrel::types::BaseType;
 use failure::Error;
 use failure::err_msg;
-use mysql::{Pool};
-use crate::{ColumnDef, DataTypes};
-use super::StorageService;
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use mysql::{LocalInfileHandler, PooledConn, Value};
+use tempfile::NamedTempFile;
+use crate::{ColumnDef, DataTypes, Records};
+use crate::parsers::values::{self, ValueParser};
+use super::{add_constraints, store_batches, table_constraints, BatchLimits, StorageService, StoredData};
 
-/// Manages interactions with a MySql database
+// a prepared statement can have at most 65,535 parameters and the server's default
+// max_allowed_packet is 4MB, batches are kept to half of it to leave room for the statement
+const MAX_PARAMS: usize = 65_535;
+const MAX_BATCH_BYTES: usize = 2 * 1024 * 1024;
+
+// the words MySQL reserves, tables and columns with these names are quoted with backticks
+const RESERVED_WORDS: &[&str] = &[
+    "accessible", "add", "all", "alter", "analyze", "and", "as", "asc", "asensitive", "before",
+    "between", "bigint", "binary", "blob", "both", "by", "call", "cascade", "case", "change",
+    "char", "character", "check", "collate", "column", "condition", "constraint", "continue",
+    "convert", "create", "cross", "cube", "cume_dist", "current_date", "current_time",
+    "current_timestamp", "current_user", "cursor", "database", "databases", "day_hour",
+    "day_microsecond", "day_minute", "day_second", "dec", "decimal", "declare", "default",
+    "delayed", "delete", "dense_rank", "desc", "describe", "deterministic", "distinct",
+    "distinctrow", "div", "double", "drop", "dual", "each", "else", "elseif", "empty", "enclosed",
+    "escaped", "except", "exists", "exit", "explain", "false", "fetch", "first_value", "float",
+    "float4", "float8", "for", "force", "foreign", "from", "fulltext", "function", "generated",
+    "get", "grant", "group", "grouping", "groups", "having", "high_priority", "hour_microsecond",
+    "hour_minute", "hour_second", "if", "ignore", "in", "index", "infile", "inner", "inout",
+    "insensitive", "insert", "int", "int1", "int2", "int3", "int4", "int8", "integer", "interval",
+    "into", "io_after_gtids", "io_before_gtids", "is", "iterate", "join", "json_table", "key",
+    "keys", "kill", "lag", "last_value", "lateral", "lead", "leading", "leave", "left", "like",
+    "limit", "linear", "lines", "load", "localtime", "localtimestamp", "lock", "long", "longblob",
+    "longtext", "loop", "low_priority", "master_bind", "master_ssl_verify_server_cert", "match",
+    "maxvalue", "mediumblob", "mediumint", "mediumtext", "middleint", "minute_microsecond",
+    "minute_second", "mod", "modifies", "natural", "not", "no_write_to_binlog", "nth_value",
+    "ntile", "null", "numeric", "of", "on", "optimize", "optimizer_costs", "option", "optionally",
+    "or", "order", "out", "outer", "outfile", "over", "partition", "percent_rank", "precision",
+    "primary", "procedure", "purge", "range", "rank", "read", "reads", "read_write", "real",
+    "recursive", "references", "regexp", "release", "rename", "repeat", "replace", "require",
+    "resignal", "restrict", "return", "revoke", "right", "rlike", "row", "rows", "row_number",
+    "schema", "schemas", "second_microsecond", "select", "sensitive", "separator", "set", "show",
+    "signal", "smallint", "spatial", "specific", "sql", "sqlexception", "sqlstate", "sqlwarning",
+    "sql_big_result", "sql_calc_found_rows", "sql_small_result", "ssl", "starting", "stored",
+    "straight_join", "system", "table", "terminated", "then", "tinyblob", "tinyint", "tinytext",
+    "to", "trailing", "trigger", "true", "undo", "union", "unique", "unlock", "unsigned", "update",
+    "usage", "use", "using", "utc_date", "utc_time", "utc_timestamp", "values", "varbinary",
+    "varchar", "varcharacter", "varying", "virtual", "when", "where", "while", "window", "with",
+    "write", "xor", "year_month", "zerofill",
+];
+
+/// Manages interactions with a MySql database, every statement is run on the same connection
+/// so they can be part of a transaction
 pub struct MySqlStore{
-    conn: Pool,
+    conn: RefCell<PooledConn>,
+    // true between the beginning of a transaction and its commit or rollback
+    in_transaction: Cell<bool>,
+    // the tables created since the transaction began, MySQL commits when it creates a table so
+    // they're dropped when the transaction is rolled back
+    created: RefCell<Vec<String>>,
 }
 
 impl MySqlStore{
     /// returns an instance of the MySqlStore which is is used to interact with a MySql
     /// database server
-    pub fn new(conn: Pool) -> Self {
-        Self{ conn }
+    pub fn new(conn: PooledConn) -> Self {
+        Self{ conn: RefCell::new(conn), in_transaction: Cell::new(false), created: RefCell::new(Vec::new()) }
     }
 
     fn exec(&self, sql_stmt: &str) -> Result<(), Error> {
-        match self.conn.prep_exec(&sql_stmt, ()) {
+        match self.conn.borrow_mut().prep_exec(&sql_stmt, ()) {
             Ok(_) => Ok(()),
             Err(e) => Err(failure::err_msg(format!("exec error: {}\n{}", e, sql_stmt)))
         }
     }
 
+    // runs a statement that can't be prepared, like the statements that start and end transactions
+    fn query(&self, sql_stmt: &str) -> Result<(), Error> {
+        match self.conn.borrow_mut().query(sql_stmt) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(failure::err_msg(format!("query error: {}\n{}", e, sql_stmt)))
+        }
+    }
+
+    // writes the table or column name the way it's created, in lowercase and quoted with
+    // backticks when it's a reserved word
+    fn identifier(name: &str) -> String {
+        let name = name.to_lowercase();
+        match RESERVED_WORDS.contains(&name.as_str()) {
+            true => format!("`{}`", name),
+            false => name,
+        }
+    }
+
     fn drop_table_sql(table_name: &str) -> Result<String, Error> {
         if table_name == "" {
             return Err(failure::err_msg("cannot drop a table schema without a name"))
         }
 
         let mut d = Migration::new();
-        d.drop_table_if_exists(table_name);
+        d.drop_table_if_exists(MySqlStore::identifier(table_name));
         Ok(format!("{};", &d.make::<MySql>()))
     }
 
+    // returns the statements that drop the tables created in a rolled back transaction, the
+    // last one created is dropped first so the tables that refer to others go before them
+    fn drop_created_sql(created: &[String]) -> Result<Vec<String>, Error> {
+        created.iter().rev().map(|name| MySqlStore::drop_table_sql(name)).collect()
+    }
+
+    // MySQL has no UUID type or timezone aware datetime type, UUIDs are stored in their
+    // text form and datetimes with a timezone are converted to UTC
+    fn to_database_type(col: &ColumnDef) -> BaseType {
+        match col.data_type {
+            DataTypes::DateTime | DataTypes::DateTimeTz => BaseType::Custom("DATETIME"),
+            DataTypes::Uuid => BaseType::Custom("CHAR(36)"),
+            _ => col.to_database_type(),
+        }
+    }
+
     fn generate_table_schema(name: String, cols: Vec<ColumnDef>) -> Result<String, Error> {
         if name == "" {
             return Err(failure::err_msg("cannot create a table schema without a name"));
@@ -46,31 +133,152 @@ impl MySqlStore{
             return Err(failure::err_msg("cannot create a table schema without at least one column"));
         }
 
+        let constraints = table_constraints(&cols, MySqlStore::identifier);
         let mut m = Migration::new();
-        m.create_table(name, move |t| {
+        m.create_table(MySqlStore::identifier(&name), move |t| {
             for cd in &cols {
-                let cname: &str = &cd.name.to_lowercase();
+                let cname: &str = &MySqlStore::identifier(&cd.name);
                 t.add_column(cname,  barrel::types::Type{
-                    nullable: true,
-                    unique: false,
+                    nullable: cd.nullable && !cd.primary_key,
+                    unique: cd.unique,
                     increments: false,
                     indexed: false,
                     default: None,
                     size: None,
-                    inner: cd.data_type.to_database_type()
+                    inner: MySqlStore::to_database_type(cd)
                 });
             }
         }).without_id();
-        let table_schema = &m.make::<MySql>();
+        let table_schema = add_constraints(m.make::<MySql>(), &constraints);
         Ok(format!("{};", table_schema))
     }
+
+    // returns the value bound to the column's parameter, numbers are read with the input's
+    // number format. Decimals are sent as text so DECIMAL columns keep every digit. Empty values,
+    // null values like NA and values that can't be read as the column's type are bound as NULL
+    fn sql_value(col: &ColumnDef, val: &str, value_parser: &ValueParser) -> Value {
+        if value_parser.stores_null(col.data_type, val) {
+            return Value::NULL;
+        }
+
+        let value = match col.data_type {
+            DataTypes::I64 => value_parser.parse_number(val)
+                .and_then(|n| n.parse::<i64>().ok())
+                .map(Value::Int),
+            DataTypes::F64 => value_parser.parse_number(val).map(|n| Value::from(n.into_owned())),
+            DataTypes::Bool => values::parse_bool(val).map(|b| Value::Int(b as i64)),
+            DataTypes::Date => value_parser.parse_date(val).and_then(|d| d.and_hms_opt(0, 0, 0)).map(MySqlStore::datetime_value),
+            DataTypes::DateTime => value_parser.parse_datetime(val).map(MySqlStore::datetime_value),
+            DataTypes::DateTimeTz => value_parser.parse_datetime_tz(val).map(|dt| MySqlStore::datetime_value(dt.naive_utc())),
+            DataTypes::Time => value_parser.parse_time(val)
+                .map(|t| Value::Time(false, 0, t.hour() as u8, t.minute() as u8, t.second() as u8, t.nanosecond() / 1_000)),
+            DataTypes::String | DataTypes::Json | DataTypes::Uuid => Some(Value::from(val)),
+            DataTypes::Empty => None,
+        };
+
+        value.unwrap_or(Value::NULL)
+    }
+
+    // returns the LOAD DATA statement that reads the columns' values from the file the local
+    // infile handler sends. The file is UTF-8, fields are quoted and backslash escaped and an
+    // unquoted \N is a NULL
+    fn load_data_sql(store_name: &str, column_defs: &[ColumnDef]) -> String {
+        let col_names: Vec<String> = column_defs.iter().map(|c| MySqlStore::identifier(&c.name)).collect();
+        format!("LOAD DATA LOCAL INFILE 'records' INTO TABLE {} CHARACTER SET utf8mb4 \
+                 FIELDS TERMINATED BY ',' OPTIONALLY ENCLOSED BY '\"' ESCAPED BY '\\\\' LINES TERMINATED BY '\\n' ({})",
+                MySqlStore::identifier(store_name), col_names.join(", "))
+    }
+
+    // writes the value bound to an insert statement the way LOAD DATA reads it so both ways of
+    // loading store the same values
+    fn load_data_value(value: Value) -> String {
+        let text = match value {
+            Value::NULL => return String::from("\\N"),
+            Value::Bytes(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Value::Int(n) => n.to_string(),
+            Value::UInt(n) => n.to_string(),
+            Value::Float(n) => n.to_string(),
+            Value::Date(year, month, day, 0, 0, 0, 0) => format!("{:04}-{:02}-{:02}", year, month, day),
+            Value::Date(year, month, day, hour, minute, second, micros) =>
+                format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}", year, month, day, hour, minute, second, micros),
+            Value::Time(negative, days, hours, minutes, seconds, micros) =>
+                format!("{}{:02}:{:02}:{:02}.{:06}", if negative { "-" } else { "" }, days * 24 + hours as u32, minutes, seconds, micros),
+        };
+
+        let mut escaped = String::with_capacity(text.len() + 2);
+        escaped.push('"');
+        for c in text.chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '"' => escaped.push_str("\\\""),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\0' => escaped.push_str("\\0"),
+                c => escaped.push(c),
+            }
+        }
+        escaped.push('"');
+        escaped
+    }
+
+    // writes the records to a temporary file for the local infile handler, the handler has to be
+    // Send and 'static and the records aren't, so they can't be read from inside of it. The file
+    // takes about as much disk space as the records
+    fn spool_records(column_defs: &[ColumnDef], data: Records, value_parser: &ValueParser) -> io::Result<(NamedTempFile, usize)> {
+        let spool = NamedTempFile::new()?;
+        let mut num_records = 0;
+        {
+            let mut writer = BufWriter::new(spool.as_file());
+            for record in data {
+                let fields: Vec<String> = record.iter()
+                    .zip(column_defs)
+                    .map(|(val, col)| MySqlStore::load_data_value(MySqlStore::sql_value(col, val, value_parser)))
+                    .collect();
+                writeln!(writer, "{}", fields.join(","))?;
+                num_records += 1;
+            }
+            writer.flush()?;
+        }
+        Ok((spool, num_records))
+    }
+
+    fn datetime_value(dt: NaiveDateTime) -> Value {
+        Value::Date(dt.year() as u16, dt.month() as u8, dt.day() as u8,
+                    dt.hour() as u8, dt.minute() as u8, dt.second() as u8, dt.nanosecond() / 1_000)
+    }
 }
 
 impl StorageService for MySqlStore {
+    fn begin_transaction(&self) -> Result<(), Error> {
+        self.query("START TRANSACTION").map_err(|e| err_msg(format!("transaction error: {}", e)))?;
+        self.in_transaction.set(true);
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> Result<(), Error> {
+        self.in_transaction.set(false);
+        self.created.borrow_mut().clear();
+        self.query("COMMIT").map_err(|e| err_msg(format!("commit error: {}", e)))
+    }
+
+    fn rollback_transaction(&self) -> Result<(), Error> {
+        self.in_transaction.set(false);
+        self.query("ROLLBACK").map_err(|e| err_msg(format!("rollback error: {}", e)))?;
+
+        // the tables were committed when they were created, dropping them undoes the rest of
+        // the transaction
+        let created = self.created.replace(Vec::new());
+        for stmt in MySqlStore::drop_created_sql(&created)? {
+            self.exec(&stmt).map_err(|e| err_msg(format!("rollback error: {}", e)))?;
+        }
+        Ok(())
+    }
+
     /// creates an insert or appropriate create statement for the backend store
-    fn create_insert_stmt(&self, store_name: String, column_defs: Vec<ColumnDef>) -> String {
-        let col_names: Vec<String> = column_defs.into_iter().map(move |c| format!("{}", c.name.clone().to_lowercase())).collect();
-        format!("INSERT INTO {} ({}) VALUES ", store_name.to_lowercase(), col_names.join(", "))
+    fn create_insert_stmt(&self, store_name: String, column_defs: Vec<ColumnDef>, num_rows: usize) -> String {
+        let row = format!("({})", vec!["?"; column_defs.len()].join(", "));
+        let col_names: Vec<String> = column_defs.into_iter().map(move |c| MySqlStore::identifier(&c.name)).collect();
+        format!("INSERT INTO {} ({}) VALUES {}", MySqlStore::identifier(&store_name), col_names.join(", "), vec![row; num_rows].join(", "))
     }
     /// describes a method that will create a table for relational databases or the equivalent in a
     /// store that is supported
@@ -100,6 +308,12 @@ impl StorageService for MySqlStore {
             Ok(stmt) => {
                 match self.exec(&stmt) {
                     Err(e) => Err(failure::err_msg(format!("table creation error: {:?}", e))),
+                    // MySQL commits the open transaction when it creates a table, the records are
+                    // loaded in a new one and the table is dropped if it's rolled back
+                    Ok(_) if self.in_transaction.get() => {
+                        self.created.borrow_mut().push(name.to_lowercase());
+                        self.begin_transaction()
+                    },
                     Ok(_) => Ok(())
                 }
             },
@@ -107,12 +321,20 @@ impl StorageService for MySqlStore {
         }
     }
 
+    fn drop_store(&self, name: String) -> Result<(), Error> {
+        let stmt = MySqlStore::drop_table_sql(&name.to_lowercase())?;
+        match self.exec(&stmt) {
+            Err(e) => Err(failure::err_msg(format!("table drop error: {:?}", e))),
+            Ok(_) => Ok(())
+        }
+    }
+
     fn delete_data_in_table(&self, name: String) -> Result<(), Error> {
         if name == "" {
             return Err(err_msg("cannot delete data from a table with an empty name"))
         }
 
-        match self.exec(&format!("delete from {};", name)) {
+        match self.exec(&format!("delete from {};", MySqlStore::identifier(&name))) {
             Err(e) => Err(failure::err_msg(format!("data deletion error: {:?}", e))),
             Ok(_) => Ok(())
         }
@@ -120,28 +342,69 @@ impl StorageService for MySqlStore {
 
     /// stores the data in the store that implements this trait, a table in relational databases but
     /// returns the number of records stored successfully or any error(s) the method encounters
-    fn store_data(&self, column_defs: Vec<ColumnDef>, data: Vec<StringRecord>, insert_stmt: String) -> Result<usize, Error> {
-        let mut rows_inserted_count = 0;
-        for line in data {
-            let mut vals: Vec<String> = Vec::new();
-            for (col_idx, rec) in line.iter().enumerate()  {
-                if column_defs[col_idx].data_type == DataTypes::String {
-                    vals.push(format!("'{}'", rec.replace("'", "''")));
-                } else if rec != "" {
-                    vals.push(rec.to_string())
-                } else {
-                    vals.push("0".to_string());
-                }
+    fn store_data(&self, store_name: String, column_defs: Vec<ColumnDef>, data: Records, batch_size: usize, commit_every: Option<usize>, value_parser: &ValueParser) -> Result<StoredData, Error> {
+        let limits = BatchLimits { max_params: MAX_PARAMS, max_bytes: Some(MAX_BATCH_BYTES) };
+
+        store_batches(data, batch_size, column_defs.len(), &limits, commit_every, |batch| {
+            let insert_stmt = self.create_insert_stmt(store_name.clone(), column_defs.clone(), batch.len());
+            let vals: Vec<Value> = batch.iter()
+                .flat_map(|line| line.iter()
+                    .zip(&column_defs)
+                    .map(|(rec, col)| MySqlStore::sql_value(col, rec, value_parser)))
+                .collect();
+
+            // the connection prepares each statement once and reuses it
+            match self.conn.borrow_mut().prep_exec(&insert_stmt, vals) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(err_msg(e.to_string())),
             }
+        }, || {
+            self.commit_transaction()?;
+            self.begin_transaction()
+        })
+    }
 
-            match self.exec(&format!("{} ({})", insert_stmt, vals.join(", "))) {
-                Err(e) => eprintln!("{}", e),
-                _ => {
-                    rows_inserted_count += 1;
-                }
+    /// loads the records with LOAD DATA LOCAL INFILE, the server skips the rows it can't store and
+    /// stores values it has to change, like strings that are too long, with a warning. The
+    /// warnings are read back so they can be reported with the table
+    fn bulk_load_data(&self, store_name: String, column_defs: Vec<ColumnDef>, data: Records, value_parser: &ValueParser) -> Result<StoredData, Error> {
+        let (spool, num_records) = match MySqlStore::spool_records(&column_defs, data, value_parser) {
+            Ok(spool) => spool,
+            Err(e) => return Err(err_msg(format!("load data error: unable to write the records: {}", e))),
+        };
+        let path = spool.path().to_path_buf();
+
+        let mut conn = self.conn.borrow_mut();
+        conn.set_local_infile_handler(Some(LocalInfileHandler::new(move |_, stream| {
+            io::copy(&mut File::open(&path)?, stream)?;
+            Ok(())
+        })));
+
+        let load_stmt = MySqlStore::load_data_sql(&store_name, &column_defs);
+        let loaded = conn.query(&load_stmt).map(|result| (result.affected_rows(), result.warnings()));
+        conn.set_local_infile_handler(None);
+        let (num_stored, num_warnings) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => return Err(err_msg(format!("load data error: {}\n{}", e, load_stmt))),
+        };
+
+        let mut warnings: Vec<String> = Vec::new();
+        if num_warnings > 0 {
+            for row in conn.query("SHOW WARNINGS")? {
+                let row = row?;
+                let level: String = row.get(0).unwrap_or_default();
+                let message: String = row.get(2).unwrap_or_default();
+                warnings.push(format!("{}: {}", level, message));
+            }
+            // the server keeps max_error_count warnings, the others are only counted
+            if warnings.len() < num_warnings as usize {
+                warnings.push(format!("{} more warnings weren't kept by the server", num_warnings as usize - warnings.len()));
             }
         }
-        Ok(rows_inserted_count)
+
+        // the rows the server skipped are the records that couldn't be stored
+        let num_stored = num_stored as usize;
+        Ok(StoredData { num_stored, num_failed: num_records.saturating_sub(num_stored), warnings, ..StoredData::default() })
     }
 }
 
@@ -153,7 +416,9 @@ impl fmt::Debug for MySqlStore {
 #[cfg(test)]
 mod tests {
     use crate::{ColumnDef, DataTypes};
+    use crate::parsers::values::ValueParser;
     use crate::storage::mysql::MySqlStore;
+    use mysql::Value;
 
     #[test]
     fn generate_table_schema_with_empty_name_returns_error() {
@@ -193,7 +458,14 @@ mod tests {
         let cols: Vec<ColumnDef>  = vec![ColumnDef{
             name: String::from("mycol"),
             data_type: DataTypes::String,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
         }];
 
         match MySqlStore::generate_table_schema(name.to_string(), cols) {
@@ -207,6 +479,125 @@ mod tests {
         }
     }
 
+    #[test]
+    fn generate_table_schema_with_new_types() {
+        let cols: Vec<ColumnDef> = vec![("flag", DataTypes::Bool), ("doc", DataTypes::Json), ("seen_at", DataTypes::DateTimeTz)].into_iter()
+            .map(|(name, data_type)| ColumnDef{
+                name: String::from(name),
+                data_type,
+                nullable: true,
+                header: None,
+                primary_key: false,
+                references: None,
+                unique: false,
+                potential_types: Vec::new(),
+                type_reason: None,
+                stats: Default::default(),
+            })
+            .collect();
+
+        let schema = MySqlStore::generate_table_schema(String::from("mine"), cols).unwrap();
+        assert_eq!(schema, String::from("CREATE TABLE mine (flag BOOLEAN, doc JSON, seen_at DATETIME);;"));
+    }
+
+    #[test]
+    fn generate_table_schema_quotes_reserved_words() {
+        let cols = vec![ColumnDef{
+            name: String::from("Order"),
+            data_type: DataTypes::String,
+            nullable: true,
+            ..ColumnDef::default()
+        }];
+
+        let schema = MySqlStore::generate_table_schema(String::from("Group"), cols).unwrap();
+        assert_eq!(schema, String::from("CREATE TABLE `group` (`order` TEXT);;"));
+    }
+
+    #[test]
+    fn sql_value_with_each_data_type() {
+        let col = |data_type| ColumnDef{
+            name: String::from("mycol"),
+            data_type,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+        };
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::String), "rob's\\", &ValueParser::default()), Value::Bytes(b"rob's\\".to_vec()));
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::I64), "", &ValueParser::default()), Value::NULL);
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::I64), "1; DROP TABLE x", &ValueParser::default()), Value::NULL);
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::F64), "NA", &ValueParser::default()), Value::NULL);
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::F64), "1234.50", &ValueParser::default()), Value::Bytes(b"1234.50".to_vec()));
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::String), "", &ValueParser::default()), Value::Bytes(Vec::new()));
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::Bool), "Y", &ValueParser::default()), Value::Int(1));
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::Date), "2019-03-01", &ValueParser::default()), Value::Date(2019, 3, 1, 0, 0, 0, 0));
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::Time), "08:15:30.5", &ValueParser::default()), Value::Time(false, 0, 8, 15, 30, 500_000));
+        assert_eq!(MySqlStore::sql_value(&col(DataTypes::Date), "", &ValueParser::default()), Value::NULL);
+    }
+
+    #[test]
+    fn load_data_value_quotes_and_escapes_values() {
+        assert_eq!(MySqlStore::load_data_value(Value::NULL), "\\N");
+        assert_eq!(MySqlStore::load_data_value(Value::Int(-45)), "\"-45\"");
+        assert_eq!(MySqlStore::load_data_value(Value::Bytes(b"say \"hi\",\nC:\\".to_vec())), "\"say \\\"hi\\\",\\nC:\\\\\"");
+        assert_eq!(MySqlStore::load_data_value(Value::Bytes(b"\\N".to_vec())), "\"\\\\N\"");
+        assert_eq!(MySqlStore::load_data_value(Value::Date(2019, 3, 1, 0, 0, 0, 0)), "\"2019-03-01\"");
+        assert_eq!(MySqlStore::load_data_value(Value::Date(2019, 3, 1, 8, 15, 0, 500)), "\"2019-03-01 08:15:00.000500\"");
+        assert_eq!(MySqlStore::load_data_value(Value::Time(false, 0, 8, 15, 30, 0)), "\"08:15:30.000000\"");
+    }
+
+    #[test]
+    fn load_data_sql_names_the_columns() {
+        let cols = vec![
+            ColumnDef { name: String::from("Name"), data_type: DataTypes::String, ..ColumnDef::default() },
+            ColumnDef { name: String::from("Order"), data_type: DataTypes::I64, ..ColumnDef::default() },
+        ];
+        assert_eq!(MySqlStore::load_data_sql("People", &cols),
+                   "LOAD DATA LOCAL INFILE 'records' INTO TABLE people CHARACTER SET utf8mb4 FIELDS TERMINATED BY ',' \
+                    OPTIONALLY ENCLOSED BY '\"' ESCAPED BY '\\\\' LINES TERMINATED BY '\\n' (name, `order`)");
+    }
+
+    #[test]
+    fn sql_value_with_datetime_tz_converts_to_utc() {
+        let col = ColumnDef{
+            name: String::from("seen_at"),
+            data_type: DataTypes::DateTimeTz,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+        };
+        assert_eq!(MySqlStore::sql_value(&col, "2019-03-01T08:15:00-05:00", &ValueParser::default()), Value::Date(2019, 3, 1, 13, 15, 0, 0));
+    }
+
+    #[test]
+    fn sql_value_binds_the_remaining_data_types_and_nulls() {
+        let col = |data_type| ColumnDef { name: String::from("mycol"), data_type, ..ColumnDef::default() };
+        let value = |data_type, val| MySqlStore::sql_value(&col(data_type), val, &ValueParser::default());
+        assert_eq!(value(DataTypes::I64, "-42"), Value::Int(-42));
+        assert_eq!(value(DataTypes::F64, "12345678901234567.25"), Value::Bytes(b"12345678901234567.25".to_vec()));
+        assert_eq!(value(DataTypes::Bool, "false"), Value::Int(0));
+        assert_eq!(value(DataTypes::DateTime, "2019-03-01 08:15:00.25"), Value::Date(2019, 3, 1, 8, 15, 0, 250_000));
+        assert_eq!(value(DataTypes::Json, "{\"tags\": [\"a\"]}"), Value::Bytes(b"{\"tags\": [\"a\"]}".to_vec()));
+        assert_eq!(value(DataTypes::Uuid, "67e55044-10b1-426f-9247-bb680e5fe0c8"), Value::Bytes(b"67e55044-10b1-426f-9247-bb680e5fe0c8".to_vec()));
+
+        // null values are bound as NULL for every type
+        for data_type in vec![DataTypes::I64, DataTypes::F64, DataTypes::Bool, DataTypes::Date, DataTypes::DateTime,
+                              DataTypes::DateTimeTz, DataTypes::Time, DataTypes::Json, DataTypes::Uuid, DataTypes::String] {
+            assert_eq!(value(data_type, "NA"), Value::NULL, "{:?}", data_type);
+        }
+        assert_eq!(value(DataTypes::Empty, "anything"), Value::NULL);
+        assert_eq!(value(DataTypes::Time, "noon"), Value::NULL);
+    }
+
     #[test]
     fn drop_table_sql_with_empty_name_returns_error() {
         match MySqlStore::drop_table_sql("") {
@@ -228,4 +619,14 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn drop_created_sql_drops_the_last_table_created_first() {
+        let created = vec![String::from("customers"), String::from("order")];
+        assert_eq!(MySqlStore::drop_created_sql(&created).unwrap(), vec![
+            String::from("DROP TABLE IF EXISTS `order`;;"),
+            String::from("DROP TABLE IF EXISTS customers;;"),
+        ]);
+        assert!(MySqlStore::drop_created_sql(&[]).unwrap().is_empty());
+    }
 }
\ No newline at end of file
@@ -1,23 +1,57 @@
 use barrel::backend::Pg;
 use barrel::*;
+use barrel::types::BaseType;
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io::{self, Read};
 
-use csv::StringRecord;
 use failure::Error;
 use failure::err_msg;
 use postgres::Connection;
-use crate::{ColumnDef, DataTypes};
-use super::StorageService;
+use postgres::stmt::Statement;
+use postgres::types::ToSql;
+use crate::{ColumnDef, DataTypes, Records};
+use crate::parsers::values::{self, ValueParser};
+use super::{add_constraints, capped_batch_size, store_batches, table_constraints, BatchLimits, StorageService, StoredData};
+
+// the most parameters an insert statement can have, the client sends the number of parameters
+// as a signed 16 bit integer
+const MAX_PARAMS: usize = 32_767;
 
 /// Manages interactions with a Postgres database
 pub struct PostgresStore{
     conn: Connection,
+    // true between the beginning of a transaction and its commit or rollback
+    in_transaction: Cell<bool>,
 }
 
 impl PostgresStore{
     /// returns an instance of the PostgresStore which is is used to interact with a Postgres
     /// database server
     pub fn new(conn: Connection) -> Self {
-        Self{ conn }
+        Self{ conn, in_transaction: Cell::new(false) }
+    }
+
+    // runs the statements in a savepoint when a transaction is open so an error only undoes
+    // them, Postgres ignores every statement after an error until the transaction is rolled back
+    fn savepoint<T, F: FnOnce() -> Result<T, Error>>(&self, statements: F) -> Result<T, Error> {
+        if !self.in_transaction.get() {
+            return statements();
+        }
+
+        self.exec("SAVEPOINT csv_to;")?;
+        match statements() {
+            Ok(value) => {
+                self.exec("RELEASE SAVEPOINT csv_to;")?;
+                Ok(value)
+            },
+            Err(e) => {
+                self.exec("ROLLBACK TO SAVEPOINT csv_to;")?;
+                Err(e)
+            },
+        }
     }
 
     /// creates a database with the given name
@@ -47,6 +81,15 @@ impl PostgresStore{
         Ok(format!("{};", &d.make::<Pg>()))
     }
 
+    // Postgres has native types for all of the data types, JSON is stored as JSONB so
+    // it can be indexed and queried
+    fn to_database_type(col: &ColumnDef) -> BaseType {
+        match col.data_type {
+            DataTypes::Json => BaseType::Custom("JSONB"),
+            _ => col.to_database_type(),
+        }
+    }
+
     fn generate_table_schema(name: String, cols: Vec<ColumnDef>) -> Result<String, Error> {
         if name == "" {
             return Err(failure::err_msg("cannot create a table schema without a name"));
@@ -56,32 +99,177 @@ impl PostgresStore{
             return Err(failure::err_msg("cannot create a table schema without at least one column"));
         }
 
+        let constraints = table_constraints(&cols, |name| format!("\"{}\"", name.to_lowercase()));
         let mut m = Migration::new();
 
         m.create_table(name, move |t| {
             for cd in &cols {
                 let cname: &str = &cd.name.to_lowercase();
                 t.add_column(cname,  barrel::types::Type{
-                    nullable: true,
-                    unique: false,
+                    nullable: cd.nullable && !cd.primary_key,
+                    unique: cd.unique,
                     increments: false,
                     indexed: false,
                     default: None,
                     size: None,
-                    inner: cd.data_type.to_database_type()
+                    inner: PostgresStore::to_database_type(cd)
                 });
             }
         }).without_id();
 
-        Ok(format!("{};", &m.make::<Pg>()))
+        Ok(format!("{};", add_constraints(m.make::<Pg>(), &constraints)))
+    }
+
+    // returns the parameter of the column's values in the insert statement. Integers are sent
+    // as BIGINTs and narrowed by the server to the column's size, decimals are sent as text so
+    // NUMERIC columns keep every digit and UUIDs are sent as text since they have no Rust type
+    fn placeholder(n: usize, col: &ColumnDef) -> String {
+        match col.data_type {
+            DataTypes::I64 => format!("${}::int8", n),
+            DataTypes::F64 => format!("${}::text::numeric", n),
+            DataTypes::Uuid => format!("${}::text::uuid", n),
+            _ => format!("${}", n),
+        }
+    }
+
+    // returns an insert statement for the number of rows, the parameters are numbered across the
+    // rows so the values of the second row start after the last column of the first
+    fn insert_sql(store_name: &str, column_defs: &[ColumnDef], num_rows: usize) -> String {
+        let rows: Vec<String> = (0..num_rows)
+            .map(|row| {
+                let placeholders: Vec<String> = column_defs.iter()
+                    .enumerate()
+                    .map(|(idx, c)| PostgresStore::placeholder(row * column_defs.len() + idx + 1, c))
+                    .collect();
+                format!("({})", placeholders.join(", "))
+            })
+            .collect();
+        let col_names: Vec<String> = column_defs.iter().map(|c| format!("\"{}\"", c.name.to_lowercase())).collect();
+        format!("INSERT INTO \"{}\" ({}) VALUES {}", store_name.to_lowercase(), col_names.join(", "), rows.join(", "))
+    }
+
+    // returns a COPY statement that reads the columns' values from CSV sent by the client, a field
+    // that isn't quoted and is empty is a NULL
+    fn copy_sql(store_name: &str, column_defs: &[ColumnDef]) -> String {
+        let col_names: Vec<String> = column_defs.iter().map(|c| format!("\"{}\"", c.name.to_lowercase())).collect();
+        format!("COPY \"{}\" ({}) FROM STDIN (FORMAT csv)", store_name.to_lowercase(), col_names.join(", "))
+    }
+
+    // returns the value the way COPY reads the column's type, it's read the same way as the
+    // values bound to the insert statements so both ways of loading store the same values. None
+    // is a NULL
+    fn copy_value(col: &ColumnDef, val: &str, value_parser: &ValueParser) -> Option<String> {
+        if value_parser.stores_null(col.data_type, val) {
+            return None;
+        }
+
+        match col.data_type {
+            DataTypes::I64 => value_parser.parse_number(val).and_then(|n| n.parse::<i64>().ok()).map(|n| n.to_string()),
+            DataTypes::F64 => value_parser.parse_number(val).map(|n| n.into_owned()),
+            DataTypes::Bool => values::parse_bool(val).map(|b| b.to_string()),
+            DataTypes::Date | DataTypes::DateTime | DataTypes::DateTimeTz | DataTypes::Time => value_parser.format_value(col.data_type, val),
+            DataTypes::Json => serde_json::from_str::<serde_json::Value>(val).ok().map(|_| val.to_string()),
+            DataTypes::String | DataTypes::Uuid | DataTypes::Empty => Some(val.to_string()),
+        }
+    }
+
+    // returns the value as the type bound to the column's parameter, numbers are read with the
+    // input's number format. Empty values, null values like NA and values that can't be read
+    // as the column's type are bound as NULLs of that type
+    fn sql_value(col: &ColumnDef, val: &str, value_parser: &ValueParser) -> Box<dyn ToSql> {
+        let val = match value_parser.stores_null(col.data_type, val) {
+            true => None,
+            false => Some(val),
+        };
+
+        match col.data_type {
+            DataTypes::I64 => Box::new(val.and_then(|v| value_parser.parse_number(v)).and_then(|n| n.parse::<i64>().ok())),
+            DataTypes::F64 => Box::new(val.and_then(|v| value_parser.parse_number(v)).map(|n| n.into_owned())),
+            DataTypes::Bool => Box::new(val.and_then(values::parse_bool)),
+            DataTypes::Date => Box::new(val.and_then(|v| value_parser.parse_date(v))),
+            DataTypes::DateTime => Box::new(val.and_then(|v| value_parser.parse_datetime(v))),
+            DataTypes::DateTimeTz => Box::new(val.and_then(|v| value_parser.parse_datetime_tz(v))),
+            DataTypes::Time => Box::new(val.and_then(|v| value_parser.parse_time(v))),
+            DataTypes::Json => Box::new(val.and_then(|v| serde_json::from_str::<serde_json::Value>(v).ok())),
+            DataTypes::String | DataTypes::Uuid | DataTypes::Empty => Box::new(val.map(String::from)),
+        }
+    }
+}
+
+// reads the records as the CSV sent to a COPY statement, each record is written when the
+// data before it has been read. Every value is quoted so an empty string isn't read as a NULL
+struct CopyData<'a> {
+    records: Records,
+    column_defs: &'a [ColumnDef],
+    value_parser: &'a ValueParser,
+    line: Vec<u8>,
+    pos: usize,
+}
+
+impl<'a> CopyData<'a> {
+    fn new(records: Records, column_defs: &'a [ColumnDef], value_parser: &'a ValueParser) -> CopyData<'a> {
+        CopyData {
+            records,
+            column_defs,
+            value_parser,
+            line: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    // writes the next record into the line, false when there are no more records
+    fn next_line(&mut self) -> bool {
+        let record = match self.records.next() {
+            Some(record) => record,
+            None => return false,
+        };
+
+        let fields: Vec<String> = record.iter()
+            .zip(self.column_defs)
+            .map(|(val, col)| match PostgresStore::copy_value(col, val, self.value_parser) {
+                Some(val) => format!("\"{}\"", val.replace('"', "\"\"")),
+                None => String::new(),
+            })
+            .collect();
+        self.line = format!("{}\n", fields.join(",")).into_bytes();
+        self.pos = 0;
+        true
+    }
+}
+
+impl<'a> Read for CopyData<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.line.len() && !self.next_line() {
+            return Ok(0);
+        }
+
+        let len = buf.len().min(self.line.len() - self.pos);
+        buf[..len].copy_from_slice(&self.line[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
     }
 }
 
 impl StorageService for PostgresStore {
+    fn begin_transaction(&self) -> Result<(), Error> {
+        self.exec("BEGIN;").map_err(|e| err_msg(format!("transaction error: {}", e)))?;
+        self.in_transaction.set(true);
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> Result<(), Error> {
+        self.in_transaction.set(false);
+        self.exec("COMMIT;").map_err(|e| err_msg(format!("commit error: {}", e)))
+    }
+
+    fn rollback_transaction(&self) -> Result<(), Error> {
+        self.in_transaction.set(false);
+        self.exec("ROLLBACK;").map_err(|e| err_msg(format!("rollback error: {}", e)))
+    }
+
     /// creates an insert or appropriate create statement for the backend store
-    fn create_insert_stmt(&self, store_name: String, column_defs: Vec<ColumnDef>) -> String{
-        let col_names: Vec<String> = column_defs.into_iter().map(move |c| format!("\"{}\"", c.name.clone().to_lowercase())).collect();
-        format!("INSERT INTO {} ({}) VALUES ", store_name.to_lowercase(), col_names.join(", "))
+    fn create_insert_stmt(&self, store_name: String, column_defs: Vec<ColumnDef>, num_rows: usize) -> String{
+        PostgresStore::insert_sql(&store_name, &column_defs, num_rows)
     }
     /// describes a method that will create a table for relational databases or the equivalent in a
     /// store that is supported
@@ -116,49 +304,94 @@ impl StorageService for PostgresStore {
         }
     }
 
+    fn drop_store(&self, name: String) -> Result<(), Error> {
+        let stmt = PostgresStore::drop_table_sql(&name.to_lowercase())?;
+        match self.exec(&stmt) {
+            Err(e) => Err(failure::err_msg(format!("table drop error: {:?}", e))),
+            Ok(_) => Ok(())
+        }
+    }
+
     fn delete_data_in_table(&self, name: String) -> Result<(), Error> {
         if name == "" {
             return Err(err_msg("cannot delete data from a table with an empty name"))
         }
 
-        match self.exec(&format!("delete from {};", name)) {
+        match self.exec(&format!("delete from \"{}\";", name.to_lowercase())) {
             Err(e) => Err(failure::err_msg(format!("data deletion error: {:?}", e))),
             Ok(_) => Ok(())
         }
     }
 
     /// stores the data in the store that implements this trait, a table in relational databases but
-    /// returns the number of records stored successfully or any error(s) the method encounters
-    fn store_data(&self, column_defs: Vec<ColumnDef>, data: Vec<StringRecord>, insert_stmt: String) -> Result<usize, Error> {
-        let mut rows_inserted_count = 0;
-        for line in data {
-            let mut vals: Vec<String> = Vec::new();
-            for (col_idx, rec) in line.iter().enumerate()  {
-                if column_defs[col_idx].data_type == DataTypes::String {
-                    vals.push(format!("'{}'", rec.replace("'", "''")));
-                } else if rec != "" {
-                        vals.push(rec.to_string())
-                } else {
-                    vals.push("0".to_string());
-                }
+    /// returns the number of records stored successfully or any error(s) the method encounters.
+    /// The statements for a full batch and a single record are prepared before the first batch is
+    /// inserted, the statement for a shorter last batch when it is needed
+    fn store_data(&self, store_name: String, column_defs: Vec<ColumnDef>, data: Records, batch_size: usize, commit_every: Option<usize>, value_parser: &ValueParser) -> Result<StoredData, Error> {
+        let limits = BatchLimits { max_params: MAX_PARAMS, max_bytes: None };
+        let max_rows = limits.max_rows(capped_batch_size(batch_size, commit_every), column_defs.len());
+        let prepare = |num_rows: usize| -> Result<Statement, Error> {
+            let insert_stmt = PostgresStore::insert_sql(&store_name, &column_defs, num_rows);
+            self.conn.prepare(&insert_stmt)
+                .map_err(|e| err_msg(format!("insert statement error: {}\n{}", e, insert_stmt)))
+        };
+
+        let mut stmts: HashMap<usize, Statement> = HashMap::new();
+        for &num_rows in &[1, max_rows] {
+            if let Entry::Vacant(entry) = stmts.entry(num_rows) {
+                entry.insert(prepare(num_rows)?);
             }
+        }
 
-            match self.exec(&format!("{} ({})", insert_stmt, vals.join(", "))) {
-                Err(e) => eprintln!("{}", e),
-                _ => {
-                    rows_inserted_count += 1;
-                }
+        store_batches(data, batch_size, column_defs.len(), &limits, commit_every, |batch| {
+            if let Entry::Vacant(entry) = stmts.entry(batch.len()) {
+                entry.insert(prepare(batch.len())?);
             }
-        }
 
-        Ok(rows_inserted_count)
+            let vals: Vec<Box<dyn ToSql>> = batch.iter()
+                .flat_map(|record| record.iter()
+                    .zip(&column_defs)
+                    .map(|(val, col)| PostgresStore::sql_value(col, val, value_parser)))
+                .collect();
+            let params: Vec<&dyn ToSql> = vals.iter().map(|val| val.as_ref()).collect();
+
+            let stmt = &stmts[&batch.len()];
+            self.savepoint(|| match stmt.execute(&params) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(err_msg(e.to_string())),
+            })
+        }, || {
+            self.commit_transaction()?;
+            self.begin_transaction()
+        })
+    }
+
+    /// streams the records to a COPY FROM STDIN statement, the whole COPY fails when one of the
+    /// records can't be stored
+    fn bulk_load_data(&self, store_name: String, column_defs: Vec<ColumnDef>, data: Records, value_parser: &ValueParser) -> Result<StoredData, Error> {
+        let copy_stmt = PostgresStore::copy_sql(&store_name, &column_defs);
+
+        self.savepoint(|| {
+            let stmt = match self.conn.prepare(&copy_stmt) {
+                Ok(stmt) => stmt,
+                Err(e) => return Err(err_msg(format!("copy statement error: {}\n{}", e, copy_stmt))),
+            };
+
+            match stmt.copy_in(&[], &mut CopyData::new(data, &column_defs, value_parser)) {
+                Ok(num_stored) => Ok(StoredData { num_stored: num_stored as usize, ..StoredData::default() }),
+                Err(e) => Err(err_msg(format!("copy error: {}", e))),
+            }
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ColumnDef, DataTypes};
-    use crate::storage::postgres::PostgresStore;
+    use crate::{ColumnDef, DataTypes, Records};
+    use crate::parsers::values::{NullValues, NumberFormat, ValueParser};
+    use crate::storage::postgres::{CopyData, PostgresStore};
+    use csv::StringRecord;
+    use std::io::Read;
 
     #[test]
     fn generate_table_schema_with_empty_name_returns_error() {
@@ -198,7 +431,14 @@ mod tests {
         let cols: Vec<ColumnDef>  = vec![ColumnDef{
             name: String::from("mycol"),
             data_type: DataTypes::String,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
         }];
 
         match PostgresStore::generate_table_schema(name.to_string(), cols) {
@@ -212,6 +452,208 @@ mod tests {
         }
     }
 
+    #[test]
+    fn generate_table_schema_with_new_types() {
+        let cols: Vec<ColumnDef> = vec![("flag", DataTypes::Bool), ("doc", DataTypes::Json), ("seen_at", DataTypes::DateTimeTz)].into_iter()
+            .map(|(name, data_type)| ColumnDef{
+                name: String::from(name),
+                data_type,
+                nullable: true,
+                header: None,
+                primary_key: false,
+                references: None,
+                unique: false,
+                potential_types: Vec::new(),
+                type_reason: None,
+                stats: Default::default(),
+            })
+            .collect();
+
+        let schema = PostgresStore::generate_table_schema(String::from("mine"), cols).unwrap();
+        assert_eq!(schema, String::from("CREATE TABLE \"mine\" (\"flag\" BOOLEAN, \"doc\" JSONB, \"seen_at\" TIMESTAMP WITH TIME ZONE);;"));
+    }
+
+    #[test]
+    fn generate_table_schema_with_sized_types() {
+        let mut count = ColumnDef::default();
+        count.name = String::from("count");
+        count.data_type = DataTypes::I64;
+        count.stats.min_int = Some(0);
+        count.stats.max_int = Some(5_000_000_000);
+
+        let mut price = ColumnDef::default();
+        price.name = String::from("price");
+        price.data_type = DataTypes::F64;
+        price.stats.int_digits = 4;
+        price.stats.scale = 2;
+
+        let mut code = ColumnDef::default();
+        code.name = String::from("code");
+        code.data_type = DataTypes::String;
+        code.stats.max_length = 5;
+
+        let schema = PostgresStore::generate_table_schema(String::from("mine"), vec![count, price, code]).unwrap();
+        assert_eq!(schema, String::from("CREATE TABLE \"mine\" (\"count\" BIGINT NOT NULL, \"price\" NUMERIC(6,2) NOT NULL, \"code\" VARCHAR(5) NOT NULL);;"));
+    }
+
+    #[test]
+    fn sql_value_with_each_data_type() {
+        let col = |data_type| ColumnDef{
+            name: String::from("mycol"),
+            data_type,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+        };
+        let value = |data_type, val| format!("{:?}", PostgresStore::sql_value(&col(data_type), val, &ValueParser::default()));
+        assert_eq!(value(DataTypes::String, "rob's"), "Some(\"rob's\")");
+        assert_eq!(value(DataTypes::I64, ""), "None");
+        assert_eq!(value(DataTypes::Bool, "Y"), "Some(true)");
+        assert_eq!(value(DataTypes::Date, "2019-03-01"), "Some(2019-03-01)");
+        assert_eq!(value(DataTypes::Date, ""), "None");
+        assert_eq!(value(DataTypes::Json, "{\"tags\": [\"a\""), "None");
+    }
+
+    #[test]
+    fn sql_value_binds_every_data_type_and_nulls() {
+        let col = |data_type| ColumnDef { name: String::from("mycol"), data_type, ..ColumnDef::default() };
+        let value = |data_type, val| format!("{:?}", PostgresStore::sql_value(&col(data_type), val, &ValueParser::default()));
+        assert_eq!(value(DataTypes::I64, "-42"), "Some(-42)");
+        assert_eq!(value(DataTypes::F64, "12345678901234567.25"), "Some(\"12345678901234567.25\")");
+        assert_eq!(value(DataTypes::Bool, "false"), "Some(false)");
+        assert_eq!(value(DataTypes::DateTime, "2019-03-01 08:15:00"), "Some(2019-03-01T08:15:00)");
+        assert_eq!(value(DataTypes::DateTimeTz, "2019-03-01T08:15:00-05:00"), "Some(2019-03-01T08:15:00-05:00)");
+        assert_eq!(value(DataTypes::Time, "08:15:30"), "Some(08:15:30)");
+        assert_eq!(value(DataTypes::Json, "{\"tags\": [\"a\"]}"), "Some(Object {\"tags\": Array [String(\"a\")]})");
+        assert_eq!(value(DataTypes::Uuid, "67e55044-10b1-426f-9247-bb680e5fe0c8"), "Some(\"67e55044-10b1-426f-9247-bb680e5fe0c8\")");
+        assert_eq!(value(DataTypes::String, ""), "Some(\"\")");
+
+        // null values are bound as NULLs of every type
+        for data_type in vec![DataTypes::I64, DataTypes::F64, DataTypes::Bool, DataTypes::Date, DataTypes::DateTime,
+                              DataTypes::DateTimeTz, DataTypes::Time, DataTypes::Json, DataTypes::Uuid, DataTypes::String] {
+            assert_eq!(value(data_type, "NA"), "None", "{:?}", data_type);
+        }
+        assert_eq!(value(DataTypes::Empty, ""), "None");
+        assert_eq!(value(DataTypes::DateTimeTz, "yesterday"), "None");
+    }
+
+    #[test]
+    fn insert_sql_numbers_the_parameters_of_each_row() {
+        let cols = vec![
+            ColumnDef { name: String::from("Name"), data_type: DataTypes::String, ..ColumnDef::default() },
+            ColumnDef { name: String::from("Age"), data_type: DataTypes::I64, ..ColumnDef::default() },
+        ];
+        assert_eq!(PostgresStore::insert_sql("People", &cols, 2),
+                   "INSERT INTO \"people\" (\"name\", \"age\") VALUES ($1, $2::int8), ($3, $4::int8)");
+    }
+
+    #[test]
+    fn copy_data_quotes_values_and_leaves_nulls_empty() {
+        let cols = vec![
+            ColumnDef { name: String::from("name"), data_type: DataTypes::String, ..ColumnDef::default() },
+            ColumnDef { name: String::from("age"), data_type: DataTypes::I64, ..ColumnDef::default() },
+            ColumnDef { name: String::from("born"), data_type: DataTypes::Date, ..ColumnDef::default() },
+        ];
+        let records: Records = Box::new(vec![
+            StringRecord::from(vec!["Rob \"the\" Dev, Jr.", "45", "1974-03-01"]),
+            StringRecord::from(vec!["", "NA", "soon"]),
+        ].into_iter());
+        let value_parser = ValueParser::default();
+
+        let mut copied = String::new();
+        CopyData::new(records, &cols, &value_parser).read_to_string(&mut copied).unwrap();
+        assert_eq!(copied, "\"Rob \"\"the\"\" Dev, Jr.\",\"45\",\"1974-03-01\"\n\"\",,\n");
+        assert_eq!(PostgresStore::copy_sql("People", &cols), "COPY \"people\" (\"name\", \"age\", \"born\") FROM STDIN (FORMAT csv)");
+    }
+
+    #[test]
+    fn copy_value_writes_every_data_type_like_the_inserts() {
+        let col = |data_type| ColumnDef { name: String::from("mycol"), data_type, ..ColumnDef::default() };
+        let value = |data_type, val| PostgresStore::copy_value(&col(data_type), val, &ValueParser::default());
+        assert_eq!(value(DataTypes::I64, "-42"), Some(String::from("-42")));
+        assert_eq!(value(DataTypes::I64, "1; DROP TABLE x"), None);
+        assert_eq!(value(DataTypes::F64, "12345678901234567.25"), Some(String::from("12345678901234567.25")));
+        assert_eq!(value(DataTypes::Bool, "Y"), Some(String::from("true")));
+        assert_eq!(value(DataTypes::DateTime, "2019-03-01T08:15:00"), Some(String::from("2019-03-01 08:15:00")));
+        assert_eq!(value(DataTypes::DateTimeTz, "2019-03-01 08:15:00"), Some(String::from("2019-03-01 08:15:00+00:00")));
+        assert_eq!(value(DataTypes::Time, "08:15"), Some(String::from("08:15:00")));
+        assert_eq!(value(DataTypes::Json, "{\"tags\": [\"a\"]}"), Some(String::from("{\"tags\": [\"a\"]}")));
+        assert_eq!(value(DataTypes::Json, "{\"tags\": [\"a\""), None);
+        assert_eq!(value(DataTypes::Uuid, "67e55044-10b1-426f-9247-bb680e5fe0c8"), Some(String::from("67e55044-10b1-426f-9247-bb680e5fe0c8")));
+        assert_eq!(value(DataTypes::String, ""), Some(String::new()));
+        assert_eq!(value(DataTypes::String, "NA"), None);
+        assert_eq!(value(DataTypes::Date, "NA"), None);
+    }
+
+    #[test]
+    fn copy_data_with_a_small_buffer() {
+        let cols = vec![
+            ColumnDef { name: String::from("name"), data_type: DataTypes::String, ..ColumnDef::default() },
+            ColumnDef { name: String::from("note"), data_type: DataTypes::String, ..ColumnDef::default() },
+        ];
+        let records: Records = Box::new(vec![
+            StringRecord::from(vec!["Rob", "line one\nline two"]),
+            StringRecord::from(vec!["Kim", ""]),
+        ].into_iter());
+        let value_parser = ValueParser::default();
+
+        // each record is handed over across as many reads as it takes
+        let mut copy_data = CopyData::new(records, &cols, &value_parser);
+        let mut copied: Vec<u8> = Vec::new();
+        let mut buf = [0u8; 3];
+        loop {
+            match copy_data.read(&mut buf).unwrap() {
+                0 => break,
+                len => copied.extend_from_slice(&buf[..len]),
+            }
+        }
+        assert_eq!(String::from_utf8(copied).unwrap(), "\"Rob\",\"line one\nline two\"\n\"Kim\",\"\"\n");
+
+        let mut empty = CopyData::new(Box::new(Vec::new().into_iter()), &cols, &value_parser);
+        assert_eq!(empty.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn placeholder_casts_numbers_and_uuids() {
+        let col = |data_type| ColumnDef { data_type, ..ColumnDef::default() };
+        assert_eq!(PostgresStore::placeholder(1, &col(DataTypes::String)), "$1");
+        assert_eq!(PostgresStore::placeholder(2, &col(DataTypes::I64)), "$2::int8");
+        assert_eq!(PostgresStore::placeholder(3, &col(DataTypes::F64)), "$3::text::numeric");
+        assert_eq!(PostgresStore::placeholder(4, &col(DataTypes::Uuid)), "$4::text::uuid");
+        assert_eq!(PostgresStore::placeholder(5, &col(DataTypes::DateTimeTz)), "$5");
+    }
+
+    #[test]
+    fn sql_value_with_locale_numbers() {
+        let col = |data_type| ColumnDef{
+            name: String::from("mycol"),
+            data_type,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+        };
+        let number_format = NumberFormat {
+            strip_currency: true,
+            strip_percent: true,
+            ..NumberFormat::for_locale("en_US").unwrap()
+        };
+        let value_parser = ValueParser::new(Vec::new(), None, false, number_format, NullValues::default());
+        let value = |data_type, val| format!("{:?}", PostgresStore::sql_value(&col(data_type), val, &value_parser));
+        assert_eq!(value(DataTypes::F64, "$1,234.56"), "Some(\"1234.56\")");
+        assert_eq!(value(DataTypes::I64, "45%"), "Some(45)");
+        assert_eq!(value(DataTypes::I64, "1; DROP TABLE x"), "None");
+    }
+
     #[test]
     fn drop_table_sql_with_empty_name_returns_error() {
         match PostgresStore::drop_table_sql("") {
@@ -1,12 +1,17 @@
 use barrel::backend::Sqlite;
-use barrel::types::Type;
+use barrel::types::{BaseType, Type};
 use barrel::*;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use failure::{Error, err_msg};
 use sqlite;
-use sqlite::{Connection, Value};
-use csv::StringRecord;
-use crate::{ColumnDef, DataTypes};
-use super::StorageService;
+use sqlite::{Connection, Statement, Value};
+use crate::{ColumnDef, DataTypes, Records};
+use crate::parsers::values::{self, ValueParser};
+use super::{add_constraints, capped_batch_size, store_batches, table_constraints, BatchLimits, StorageService, StoredData};
+
+// the most parameters a statement can have in SQLite versions before 3.32
+const MAX_PARAMS: usize = 999;
 
 /// The adapter that handles the interactions with a SQLite store
 pub struct SQLiteStore{
@@ -38,6 +43,7 @@ impl SQLiteStore {
         if cols.is_empty() {
             return Err(failure::err_msg("Cannot create a table with no columns"));
         }
+        let constraints = table_constraints(&cols, |name| format!("\"{}\"", name));
         let mut m = Migration::new();
 
         if drop_table_if_exists {
@@ -48,59 +54,106 @@ impl SQLiteStore {
             for cd in &cols {
                 let cname: &str = &cd.name;
                 t.add_column(cname, Type{
-                    nullable: true,
-                    unique: false,
+                    nullable: cd.nullable && !cd.primary_key,
+                    unique: cd.unique,
                     increments: false,
                     indexed: false,
                     default: None,
                     size: None,
-                    inner: cd.data_type.to_database_type()
+                    inner: SQLiteStore::to_database_type(cd.data_type)
                 });
             }
         }).without_id();
 
-        Ok(format!("{};", &m.make::<Sqlite>()))
+        Ok(format!("{};", add_constraints(m.make::<Sqlite>(), &constraints)))
+    }
+
+    // SQLite has no datetime, UUID or JSON types. Dates and times are stored as ISO-8601 text,
+    // which SQLite's date and time functions read, in columns declared as DATE, DATETIME or
+    // TIME so the intent of the column isn't lost. SQLite ignores the sizes of types so the
+    // columns aren't sized to their values
+    fn to_database_type(data_type: DataTypes) -> BaseType {
+        match data_type {
+            DataTypes::DateTime | DataTypes::DateTimeTz => BaseType::Custom("DATETIME"),
+            DataTypes::Json | DataTypes::Uuid => BaseType::Text,
+            _ => data_type.to_database_type(),
+        }
     }
 
     // get_value_type converts the given col_val to appropriate type for
-    // the col provided.  For numeric columns if a non integer or float is
-    // provided in col_value the value of 0 or 0.0 will be returned.
-    fn get_value_type(col: &ColumnDef, col_value: String) -> sqlite::Value {
+    // the col provided.  Numbers are read with the input's number format,
+    // empty values, null values like NA and values that can't be read as the
+    // column's type are stored as NULL. Booleans are stored as 1 or 0 and
+    // dates and times as ISO-8601 text
+    fn get_value_type(col: &ColumnDef, col_value: String, value_parser: &ValueParser) -> sqlite::Value {
+        if value_parser.stores_null(col.data_type, &col_value) {
+            return Value::Null;
+        }
+
         match col.data_type {
             DataTypes::String => Value::String(col_value),
             DataTypes::I64 => {
-                let value = match col_value.parse::<i64>() {
-                    Ok(v) => v,
-                    Err(_) => {
-                        0
-                    }
-                };
-                Value::Integer(value)
+                match value_parser.parse_number(&col_value).map(|n| n.parse::<i64>()) {
+                    Some(Ok(v)) => Value::Integer(v),
+                    _ => Value::Null,
+                }
             },
             DataTypes::F64 => {
-                let value = match col_value.parse::<f64>() {
-                    Ok(v) => v,
-                    Err(_) => {
-                        0.0
-                    }
-                };
-                Value::Float(value)
+                match value_parser.parse_number(&col_value).map(|n| n.parse::<f64>()) {
+                    Some(Ok(v)) => Value::Float(v),
+                    _ => Value::Null,
+                }
+            },
+            DataTypes::Bool => {
+                match values::parse_bool(&col_value) {
+                    Some(v) => Value::Integer(v as i64),
+                    None => Value::Null,
+                }
+            },
+            DataTypes::Date | DataTypes::DateTime | DataTypes::DateTimeTz | DataTypes::Time => {
+                match value_parser.format_value(col.data_type, &col_value) {
+                    Some(v) => Value::String(v),
+                    None => Value::Null,
+                }
             },
+            DataTypes::Json | DataTypes::Uuid => Value::String(col_value),
             DataTypes::Empty => Value::Null
         }
     }
+
+    // returns an insert statement for the number of rows, the parameters are numbered across the
+    // rows so the values of the second row start after the last column of the first
+    fn insert_sql(store_name: &str, column_defs: &[ColumnDef], num_rows: usize) -> String {
+        let rows: Vec<String> = (0..num_rows)
+            .map(|row| {
+                let placeholders: Vec<String> = (0..column_defs.len())
+                    .map(|n| format!("?{}", row * column_defs.len() + n + 1))
+                    .collect();
+                format!("({})", placeholders.join(", "))
+            })
+            .collect();
+
+        let col_names: Vec<String> = column_defs.iter().map(|c| format!("\"{}\"", c.name)).collect();
+        format!("INSERT INTO \"{}\" ({}) VALUES {}", store_name, col_names.join(", "), rows.join(", "))
+    }
 }
 
 impl StorageService for SQLiteStore {
-    // Generates a string that contains the SQL for inserting a row into the given table
-    fn create_insert_stmt(&self, store_name: String, column_defs: Vec<ColumnDef>) -> String {
-        let mut placeholders: Vec<String> = Vec::new();
-        for n in 0..column_defs.len() {
-            placeholders.push(format!("?{}", n+1));
-        }
+    fn begin_transaction(&self) -> Result<(), Error> {
+        self.conn.execute("BEGIN;").map_err(|e| err_msg(format!("transaction error: {}", e)))
+    }
 
-        let col_names: Vec<String> = column_defs.into_iter().map(move |c| c.name.clone()).collect();
-        format!("INSERT INTO {} ({}) VALUES ({})", store_name, col_names.join(", "), placeholders.join(", "))
+    fn commit_transaction(&self) -> Result<(), Error> {
+        self.conn.execute("COMMIT;").map_err(|e| err_msg(format!("commit error: {}", e)))
+    }
+
+    fn rollback_transaction(&self) -> Result<(), Error> {
+        self.conn.execute("ROLLBACK;").map_err(|e| err_msg(format!("rollback error: {}", e)))
+    }
+
+    // Generates a string that contains the SQL for inserting the number of rows into the given table
+    fn create_insert_stmt(&self, store_name: String, column_defs: Vec<ColumnDef>, num_rows: usize) -> String {
+        SQLiteStore::insert_sql(&store_name, &column_defs, num_rows)
     }
 
     /// Creates the table with the given name that will store the data from the related input file
@@ -123,14 +176,25 @@ impl StorageService for SQLiteStore {
             Err(e) => Err(e)
         }
     }
+    fn drop_store(&self, name: String) -> Result<(), Error> {
+        if name == "" {
+            return Err(err_msg("cannot drop a table with an empty name"))
+        }
 
+        let mut m = Migration::new();
+        m.drop_table_if_exists(name);
+        match self.conn.execute(&format!("{};", m.make::<Sqlite>())) {
+            Err(e) => Err(failure::err_msg(format!("table drop error: {:?}", e))),
+            Ok(_) => Ok(())
+        }
+    }
 
     fn delete_data_in_table(&self, name: String) -> Result<(), Error> {
         if name == "" {
             return Err(err_msg("cannot delete data from a table with an empty name"))
         }
 
-        match self.conn.execute(&format!("delete from {};", name)) {
+        match self.conn.execute(&format!("delete from \"{}\";", name)) {
             Err(e) => Err(failure::err_msg(format!("data deletion error: {:?}", e))),
             Ok(_) => Ok(())
         }
@@ -139,32 +203,52 @@ impl StorageService for SQLiteStore {
 
     /// stores the data in the store that implements this trait, a table in relational databases but
     /// returns the number of records stored successfully or any error(s) the method encounters
-    fn store_data(&self, column_defs: Vec<ColumnDef>, content: Vec<StringRecord>, insert_stmt: String) -> Result<usize, failure::Error> {
-        let mut rows_inserted_count = 0;
-        let mut stmt = self.conn.prepare(insert_stmt).unwrap();
-
-        for vrec in content {
-            stmt.reset().unwrap();
-            rows_inserted_count += 1;
-
-            let mut col_idx: usize = 1;
-            for c in vrec.iter() {
-                let value = &SQLiteStore::get_value_type(&column_defs[col_idx - 1], c.to_string());
-                stmt.bind(col_idx, value).unwrap();
-                col_idx += 1;
-            }
+    fn store_data(&self, store_name: String, column_defs: Vec<ColumnDef>, content: Records, batch_size: usize, commit_every: Option<usize>, value_parser: &ValueParser) -> Result<StoredData, failure::Error> {
+        let limits = BatchLimits { max_params: MAX_PARAMS, max_bytes: None };
+        let max_rows = limits.max_rows(capped_batch_size(batch_size, commit_every), column_defs.len());
+        let prepare = |num_rows: usize| -> Result<Statement, Error> {
+            let insert_stmt = SQLiteStore::insert_sql(&store_name, &column_defs, num_rows);
+            self.conn.prepare(&insert_stmt)
+                .map_err(|e| err_msg(format!("insert statement error: {}\n{}", e, insert_stmt)))
+        };
 
-            let _results = stmt.next().unwrap();
+        let mut stmts: HashMap<usize, Statement> = HashMap::new();
+        for &num_rows in &[1, max_rows] {
+            if let Entry::Vacant(entry) = stmts.entry(num_rows) {
+                entry.insert(prepare(num_rows)?);
+            }
         }
 
-        Ok(rows_inserted_count)
+        store_batches(content, batch_size, column_defs.len(), &limits, commit_every, |batch| {
+            let stmt = match stmts.entry(batch.len()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(prepare(batch.len())?),
+            };
+            stmt.reset()?;
+
+            let values = batch.iter().flat_map(|vrec| vrec.iter().zip(&column_defs));
+            for (idx, (c, col)) in values.enumerate() {
+                stmt.bind(idx + 1, &SQLiteStore::get_value_type(col, c.to_string(), value_parser))?;
+            }
+
+            while let sqlite::State::Row = stmt.next()? {}
+            Ok(())
+        }, || {
+            self.commit_transaction()?;
+            self.begin_transaction()
+        })
+    }
+
+    fn bulk_load_data(&self, _store_name: String, _column_defs: Vec<ColumnDef>, _data: Records, _value_parser: &ValueParser) -> Result<StoredData, Error> {
+        Err(err_msg("SQLite has no bulk loader, the records have to be inserted"))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::storage::sqlite::SQLiteStore;
-    use crate::{ColumnDef, DataTypes};
+    use crate::{ColumnDef, DataTypes, Reference};
+    use crate::parsers::values::{NullValues, NumberFormat, ValueParser};
     use sqlite::Value;
 
     //==================================================
@@ -174,10 +258,17 @@ mod tests {
         let test_val = String::from("hi");
         let cd = ColumnDef{
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
             name: String::from("mycol"),
             data_type: DataTypes::String,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
         };
-        let v: sqlite::Value = SQLiteStore::get_value_type(&cd, test_val.clone());
+        let v: sqlite::Value = SQLiteStore::get_value_type(&cd, test_val.clone(), &ValueParser::default());
         assert_eq!(v, Value::String(test_val));
     }
 
@@ -186,10 +277,17 @@ mod tests {
         let test_val = 1.23;
         let cd = ColumnDef{
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
             name: String::from("mycol"),
             data_type: DataTypes::F64,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
         };
-        let v: sqlite::Value = SQLiteStore::get_value_type(&cd, test_val.to_string());
+        let v: sqlite::Value = SQLiteStore::get_value_type(&cd, test_val.to_string(), &ValueParser::default());
         assert_eq!(v, Value::Float(test_val));
     }
 
@@ -198,25 +296,221 @@ mod tests {
         let test_val = 123;
         let cd = ColumnDef{
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
             name: String::from("mycol"),
             data_type: DataTypes::I64,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
         };
-        let v: sqlite::Value = SQLiteStore::get_value_type(&cd, test_val.to_string());
+        let v: sqlite::Value = SQLiteStore::get_value_type(&cd, test_val.to_string(), &ValueParser::default());
         assert_eq!(v, Value::Integer(test_val));
     }
 
+    #[test]
+    fn get_value_type_with_locale_numbers() {
+        let number_format = NumberFormat {
+            strip_currency: true,
+            ..NumberFormat::for_locale("de_DE").unwrap()
+        };
+        let value_parser = ValueParser::new(Vec::new(), None, false, number_format, NullValues::default());
+        let cd = |data_type| ColumnDef{
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+            name: String::from("mycol"),
+            data_type,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+        };
+        assert_eq!(SQLiteStore::get_value_type(&cd(DataTypes::F64), String::from("1.234,5 €"), &value_parser), Value::Float(1234.5));
+        assert_eq!(SQLiteStore::get_value_type(&cd(DataTypes::I64), String::from("1.234"), &value_parser), Value::Integer(1234));
+    }
+
+    #[test]
+    fn get_value_type_with_empty_and_null_values() {
+        let cd = |data_type| ColumnDef{
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+            name: String::from("mycol"),
+            data_type,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+        };
+        assert_eq!(SQLiteStore::get_value_type(&cd(DataTypes::I64), String::new(), &ValueParser::default()), Value::Null);
+        assert_eq!(SQLiteStore::get_value_type(&cd(DataTypes::F64), String::from("NA"), &ValueParser::default()), Value::Null);
+        assert_eq!(SQLiteStore::get_value_type(&cd(DataTypes::I64), String::from("twelve"), &ValueParser::default()), Value::Null);
+        assert_eq!(SQLiteStore::get_value_type(&cd(DataTypes::String), String::from("\\N"), &ValueParser::default()), Value::Null);
+        assert_eq!(SQLiteStore::get_value_type(&cd(DataTypes::String), String::new(), &ValueParser::default()), Value::String(String::new()));
+
+        let null_values = NullValues {
+            values: Vec::new(),
+            empty_strings: true,
+        };
+        let value_parser = ValueParser::new(Vec::new(), None, false, NumberFormat::default(), null_values);
+        assert_eq!(SQLiteStore::get_value_type(&cd(DataTypes::String), String::new(), &value_parser), Value::Null);
+    }
+
     #[test]
     fn get_value_type_with_empty() {
         let test_val = 123;
         let cd = ColumnDef{
             potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
             name: String::from("mycol"),
             data_type: DataTypes::Empty,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
         };
-        let v: sqlite::Value = SQLiteStore::get_value_type(&cd, test_val.to_string());
+        let v: sqlite::Value = SQLiteStore::get_value_type(&cd, test_val.to_string(), &ValueParser::default());
         assert_eq!(v, Value::Null);
     }
 
+    #[test]
+    fn get_value_type_with_bool() {
+        let cd = ColumnDef{
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+            name: String::from("mycol"),
+            data_type: DataTypes::Bool,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+        };
+        assert_eq!(SQLiteStore::get_value_type(&cd, String::from("Yes"), &ValueParser::default()), Value::Integer(1));
+        assert_eq!(SQLiteStore::get_value_type(&cd, String::from("false"), &ValueParser::default()), Value::Integer(0));
+    }
+
+    #[test]
+    fn get_value_type_with_date() {
+        let cd = ColumnDef{
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+            name: String::from("mycol"),
+            data_type: DataTypes::Date,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+        };
+        assert_eq!(SQLiteStore::get_value_type(&cd, String::from("2019-03-01"), &ValueParser::default()), Value::String(String::from("2019-03-01")));
+        assert_eq!(SQLiteStore::get_value_type(&cd, String::new(), &ValueParser::default()), Value::Null);
+    }
+
+    #[test]
+    fn get_value_type_with_configured_date_format_and_utc() {
+        let value_parser = ValueParser::new(vec!["%m/%d/%Y %H:%M".parse().unwrap()], Some("America/Chicago".parse().unwrap()), true, NumberFormat::default(), NullValues::default());
+        let cd = ColumnDef{
+            potential_types: Vec::new(),
+            type_reason: None,
+            stats: Default::default(),
+            name: String::from("mycol"),
+            data_type: DataTypes::DateTimeTz,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
+        };
+        assert_eq!(SQLiteStore::get_value_type(&cd, String::from("03/01/2019 08:15"), &value_parser), Value::String(String::from("2019-03-01 14:15:00+00:00")));
+    }
+
+    #[test]
+    fn generate_table_schema_with_new_types_returns_proper_ddl() {
+        let column_defs: Vec<ColumnDef> = vec![DataTypes::Bool, DataTypes::DateTimeTz, DataTypes::Uuid].into_iter()
+            .enumerate()
+            .map(|(idx, data_type)| ColumnDef{
+                data_type,
+                nullable: true,
+                header: None,
+                primary_key: false,
+                references: None,
+                unique: false,
+                name: format!("Col{}", idx + 1),
+                potential_types: vec![data_type],
+                type_reason: None,
+                stats: Default::default(),
+            })
+            .collect();
+
+        let sql = SQLiteStore::generate_table_schema(String::from("mytable"), column_defs, false).unwrap();
+        assert_eq!(sql, String::from("CREATE TABLE \"mytable\" (\"Col1\" BOOLEAN, \"Col2\" DATETIME, \"Col3\" TEXT);;"));
+    }
+
+    #[test]
+    fn generate_table_schema_with_not_null_columns() {
+        let column_defs: Vec<ColumnDef> = vec![("id", false), ("note", true)].into_iter()
+            .map(|(name, nullable)| ColumnDef{
+                data_type: DataTypes::String,
+                nullable,
+                header: None,
+                primary_key: false,
+                references: None,
+                unique: false,
+                name: String::from(name),
+                potential_types: Vec::new(),
+                type_reason: None,
+                stats: Default::default(),
+            })
+            .collect();
+
+        let sql = SQLiteStore::generate_table_schema(String::from("mytable"), column_defs, false).unwrap();
+        assert_eq!(sql, String::from("CREATE TABLE \"mytable\" (\"id\" TEXT NOT NULL, \"note\" TEXT);;"));
+    }
+
+    #[test]
+    fn generate_table_schema_with_primary_key_and_unique_columns() {
+        let column_defs: Vec<ColumnDef> = vec![("year", true, false), ("month", true, false), ("code", false, true)].into_iter()
+            .map(|(name, primary_key, unique)| ColumnDef{
+                data_type: DataTypes::I64,
+                nullable: true,
+                header: None,
+                primary_key,
+                references: None,
+                unique,
+                name: String::from(name),
+                potential_types: Vec::new(),
+                type_reason: None,
+                stats: Default::default(),
+            })
+            .collect();
+
+        let sql = SQLiteStore::generate_table_schema(String::from("mytable"), column_defs, false).unwrap();
+        assert_eq!(sql, String::from("CREATE TABLE \"mytable\" (\"year\" INTEGER NOT NULL, \"month\" INTEGER NOT NULL, \"code\" INTEGER UNIQUE, PRIMARY KEY (\"year\", \"month\"));;"));
+    }
+
+    #[test]
+    fn generate_table_schema_with_foreign_key() {
+        let column_defs = vec![ColumnDef{
+            data_type: DataTypes::I64,
+            name: String::from("customer_id"),
+            references: Some(Reference { table: String::from("Customers"), column: String::from("id") }),
+            ..ColumnDef::default()
+        }];
+
+        let sql = SQLiteStore::generate_table_schema(String::from("Orders"), column_defs, false).unwrap();
+        assert_eq!(sql, String::from("CREATE TABLE \"Orders\" (\"customer_id\" INTEGER NOT NULL, FOREIGN KEY (\"customer_id\") REFERENCES \"Customers\" (\"id\"));;"));
+    }
+
     #[test]
     fn generate_table_schema_with_empty_table_name_ret() {
         let name = String::new();
@@ -250,13 +544,27 @@ mod tests {
         let name = String::from("mytable");
         let c1 = ColumnDef{
             data_type: DataTypes::String,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             name: String::from("Col1"),
             potential_types: vec![DataTypes::String],
+            type_reason: None,
+            stats: Default::default(),
         };
         let c2 = ColumnDef{
             data_type: DataTypes::I64,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             name: String::from("Col2"),
             potential_types: vec![DataTypes::I64],
+            type_reason: None,
+            stats: Default::default(),
         };
         let column_defs = vec![c1, c2];
 
@@ -274,13 +582,27 @@ mod tests {
         let name = String::from("mytable");
         let c1 = ColumnDef{
             data_type: DataTypes::String,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             name: String::from("Col1"),
             potential_types: vec![DataTypes::String],
+            type_reason: None,
+            stats: Default::default(),
         };
         let c2 = ColumnDef{
             data_type: DataTypes::I64,
+            nullable: true,
+            header: None,
+            primary_key: false,
+            references: None,
+            unique: false,
             name: String::from("Col2"),
             potential_types: vec![DataTypes::I64],
+            type_reason: None,
+            stats: Default::default(),
         };
         let column_defs = vec![c1, c2];
 
@@ -292,4 +614,15 @@ mod tests {
             Err(e) => assert_eq!(format!("{}", e), "Cannot create a table with no columns")
         }
     }
+
+    #[test]
+    fn insert_sql_numbers_the_parameters_of_each_row() {
+        let cols = vec![
+            ColumnDef { name: String::from("Name"), data_type: DataTypes::String, ..ColumnDef::default() },
+            ColumnDef { name: String::from("Age"), data_type: DataTypes::I64, ..ColumnDef::default() },
+        ];
+        assert_eq!(SQLiteStore::insert_sql("People", &cols, 1), "INSERT INTO \"People\" (\"Name\", \"Age\") VALUES (?1, ?2)");
+        assert_eq!(SQLiteStore::insert_sql("People", &cols, 3),
+                   "INSERT INTO \"People\" (\"Name\", \"Age\") VALUES (?1, ?2), (?3, ?4), (?5, ?6)");
+    }
 }
\ No newline at end of file
@@ -59,24 +59,124 @@ USAGE:
     csv-to db [FLAGS] [OPTIONS] --connection-info <connection_info> --type <db_type> --name <name>
 
 FLAGS:
-        --delete-data    deletes the data from the tables, keeps the table's schema
-        --drop-stores    Drops tables/collections if the already exist
-    -h, --help           Prints help information
-        --no-headers     The CSV file(s) have no column headers
-    -s, --save-cache     Stores the meta data about each input's column data definitions
-    -V, --version        Prints version information
+        --auto-primary-key    Makes the first column, or pair of columns, whose values are all different and never empty
+                              the primary key of each table when --primary-key isn't given. The other columns with all
+                              different values are made UNIQUE
+        --bulk-load           Loads the records with the database's bulk loader, COPY FROM STDIN with Postgres or LOAD
+                              DATA LOCAL INFILE with MySQL, which is much faster than inserts. When a file can't be bulk
+                              loaded its records are inserted to find the ones that fail. MySQL's records are written to
+                              a temporary file first, it needs about as much free disk space as the file being loaded
+        --delete-data         deletes the data from the tables, keeps the table's schema
+        --drop-stores         Drops tables/collections if the already exist
+        --empty-as-null       Stores empty values in string columns as NULL instead of empty strings, empty values in
+                              other columns are always NULL
+        --fixed-width-ids     Stores the columns whose values are all the same number of digits, 5 or more, as strings
+                              like phone numbers. Without it only values with leading zeros or too large for a 64-bit
+                              integer are stored as strings
+        --foreign-keys        Creates FOREIGN KEY constraints for the columns whose values are all in the primary key or
+                              a UNIQUE column of another file's table, the tables they refer to are loaded first
+    -h, --help                Prints help information
+        --no-double-quote     Two quote characters inside of a quoted field are not read as a single quote
+        --no-headers          The CSV file(s) have no column headers. Without it each file's first row is a header
+                              unless its values look like the values of the rows that follow it
+        --nullable            Creates every column as nullable, otherwise columns that never have an empty value are NOT
+                              NULL
+    -s, --save-cache          Stores the meta data about each input's column data definitions
+        --sniff               Detects each file's delimiter, quoting and header row from a sample of the file, the ones
+                              saved in the cache by an earlier run are used when there are any
+        --strip-currency      Removes currency symbols like $ and € before or after numbers so they are stored as
+                              numbers
+        --strip-percent       Removes the percent sign after numbers so they are stored as numbers, 45% is stored as 45
+        --utc                 Converts dates and times with a timezone to UTC before they are stored
+    -V, --version             Prints version information
 
 OPTIONS:
-    -c, --connection-info <connection_info>    Database connectivity information
-    -t, --type <db_type>                       The type of database to create, valid types are sqlite, postgres, and
-                                               mysql
-    -d, --directories <directories>...         The directories that contain CSV files to be processed, a comma delimited
-                                               string of paths
-    -e, --extension <extension>                the file extension for the CSV files to be parsed [default: csv]
-    -f, --files <files>...                     The CSV files to be processed, can be /path/to/files/ or a comma
-                                               delimited string of paths
-    -n, --name <name>                          Name of the database to be created
-    -o, --one-table <one_table>                Store the parsed data in a single table with the name given here
+        --batch-size <batch_size>
+            The most records inserted with a single statement, 1000 when not given. Batches are made smaller when the
+            database can't take that many values or bytes in one statement. A batch that fails is inserted one record at
+            a time
+        --comment <comment>                       Lines that start with this character are ignored
+    -c, --connection-info <connection_info>       Database connectivity information
+        --date-format <date_formats>...
+            Additional date/time formats to recognize, strftime patterns like %m/%d/%Y, or rfc2822, rfc3339, or epoch.
+            ISO-8601 dates and times are always recognized
+    -t, --type <db_type>
+            The type of database to create, valid types are sqlite, postgres, and mysql
+
+        --delimiter <delimiter>
+            The field delimiter, use 'tab' for tab delimited files. A comma when not given
+
+    -d, --directories <directories>...
+            The directories that contain CSV files to be processed, a comma delimited string of paths
+
+        --encoding <encoding>
+            The character encoding of the CSV file(s), e.g. utf-8, latin1, or utf-16le, detected for each file when not
+            given
+        --escape <escape>                         The character used to escape quotes inside of quoted fields
+    -e, --extension <extension>
+            the file extension for the CSV files to be parsed, can be a comma delimited list of extensions [default:
+            csv]
+    -f, --files <files>...
+            The CSV files to be processed, can be /path/to/files/ or a comma delimited string of paths, use - to read
+            from stdin
+        --header-case <header_case>
+            The case of the column names made from the headers, valid cases are keep, snake, and camel, keep when not
+            given. Accented letters lose their accents, other characters that can't be in a name become underscores and
+            repeated names are numbered
+        --header-files <header_files>...
+            Files that have a header row, their first row isn't checked, a comma delimited list of file names or paths
+
+        --header-row <header_row>
+            The row the header is on, counted from 1 after the lines skipped with --skip-lines, the rows above it are
+            skipped. The first row of data when there are no headers
+        --locale <locale>
+            The locale numbers are written in, e.g. en_US for 1,234.56 or de_DE for 1.234,56. Without it numbers have no
+            thousands separators and a . before the decimals
+        --max-errors <max_errors>
+            The most records of a transaction that can fail to be stored, the transaction is rolled back when more of
+            its records fail so a rerun starts clean. Without it a transaction is only rolled back when loading stops
+            with an error
+    -n, --name <name>
+            Name of the database to be created, also the table name for data read from stdin
+
+        --no-header-files <no_header_files>...
+            Files that have no header row, their first row isn't checked, a comma delimited list of file names or paths
+
+        --null-values <null_values>...
+            The values that are stored as NULL, a comma delimited list. NA, NULL, \\N, and - when not given
+
+        --numeric-columns <numeric_columns>...
+            Columns to store as numbers even when their values look like identifiers, values with leading zeros or, with
+            --fixed-width-ids, that are all the same number of digits are stored as strings otherwise, a comma delimited
+            list of column names
+    -o, --one-table <one_table>                   Store the parsed data in a single table with the name given here
+        --primary-key <primary_key>...
+            The columns of each table's primary key, a comma delimited list of column names
+
+        --quote <quote>                           The character used to quote fields. A double quote when not given
+        --ragged-rows <ragged_rows>
+            What is done with rows that have more or fewer fields than the header, valid options are strict, pad,
+            truncate, and side-file, strict when not given. strict skips them, pad stores missing fields as NULL,
+            truncate also drops extra fields and side-file writes them to a .bad.csv file next to the input
+        --skip-footer <skip_footer>
+            The number of rows at the end of each file that are dropped, like totals or a generated on line
+
+        --skip-lines <skip_lines>
+            The number of lines at the beginning of each file that are skipped before the header, like titles or notes,
+            the lines don't have to be CSV
+        --skip-pattern <skip_pattern>
+            Rows that match this regular expression are dropped, the row's fields are matched as they are in the file
+            joined by the delimiter, e.g. ^Total,
+        --timezone <timezone>
+            The timezone of dates and times that don't have one, e.g. America/New_York or UTC. Without it they are
+            stored without a timezone
+        --transaction <transaction_mode>
+            How the tables and records are committed, valid modes are file, run, or a number of records, file when not
+            given. file commits each file's table and records together, run commits every file together at the end and a
+            number commits each file's records every that many records
+        --trim <trim>
+            Whitespace to trim, valid options are none, headers, fields, and all, none when not given
+
 ", env!("CARGO_PKG_VERSION"));;
 
     let output = Command::new(CMD_PATH)
@@ -98,24 +198,124 @@ USAGE:
     csv-to db [FLAGS] [OPTIONS] --connection-info <connection_info> --type <db_type> --name <name>
 
 FLAGS:
-        --delete-data    deletes the data from the tables, keeps the table's schema
-        --drop-stores    Drops tables/collections if the already exist
-    -h, --help           Prints help information
-        --no-headers     The CSV file(s) have no column headers
-    -s, --save-cache     Stores the meta data about each input's column data definitions
-    -V, --version        Prints version information
+        --auto-primary-key    Makes the first column, or pair of columns, whose values are all different and never empty
+                              the primary key of each table when --primary-key isn't given. The other columns with all
+                              different values are made UNIQUE
+        --bulk-load           Loads the records with the database's bulk loader, COPY FROM STDIN with Postgres or LOAD
+                              DATA LOCAL INFILE with MySQL, which is much faster than inserts. When a file can't be bulk
+                              loaded its records are inserted to find the ones that fail. MySQL's records are written to
+                              a temporary file first, it needs about as much free disk space as the file being loaded
+        --delete-data         deletes the data from the tables, keeps the table's schema
+        --drop-stores         Drops tables/collections if the already exist
+        --empty-as-null       Stores empty values in string columns as NULL instead of empty strings, empty values in
+                              other columns are always NULL
+        --fixed-width-ids     Stores the columns whose values are all the same number of digits, 5 or more, as strings
+                              like phone numbers. Without it only values with leading zeros or too large for a 64-bit
+                              integer are stored as strings
+        --foreign-keys        Creates FOREIGN KEY constraints for the columns whose values are all in the primary key or
+                              a UNIQUE column of another file's table, the tables they refer to are loaded first
+    -h, --help                Prints help information
+        --no-double-quote     Two quote characters inside of a quoted field are not read as a single quote
+        --no-headers          The CSV file(s) have no column headers. Without it each file's first row is a header
+                              unless its values look like the values of the rows that follow it
+        --nullable            Creates every column as nullable, otherwise columns that never have an empty value are NOT
+                              NULL
+    -s, --save-cache          Stores the meta data about each input's column data definitions
+        --sniff               Detects each file's delimiter, quoting and header row from a sample of the file, the ones
+                              saved in the cache by an earlier run are used when there are any
+        --strip-currency      Removes currency symbols like $ and € before or after numbers so they are stored as
+                              numbers
+        --strip-percent       Removes the percent sign after numbers so they are stored as numbers, 45% is stored as 45
+        --utc                 Converts dates and times with a timezone to UTC before they are stored
+    -V, --version             Prints version information
 
 OPTIONS:
-    -c, --connection-info <connection_info>    Database connectivity information
-    -t, --type <db_type>                       The type of database to create, valid types are sqlite, postgres, and
-                                               mysql
-    -d, --directories <directories>...         The directories that contain CSV files to be processed, a comma delimited
-                                               string of paths
-    -e, --extension <extension>                the file extension for the CSV files to be parsed [default: csv]
-    -f, --files <files>...                     The CSV files to be processed, can be /path/to/files/ or a comma
-                                               delimited string of paths
-    -n, --name <name>                          Name of the database to be created
-    -o, --one-table <one_table>                Store the parsed data in a single table with the name given here
+        --batch-size <batch_size>
+            The most records inserted with a single statement, 1000 when not given. Batches are made smaller when the
+            database can't take that many values or bytes in one statement. A batch that fails is inserted one record at
+            a time
+        --comment <comment>                       Lines that start with this character are ignored
+    -c, --connection-info <connection_info>       Database connectivity information
+        --date-format <date_formats>...
+            Additional date/time formats to recognize, strftime patterns like %m/%d/%Y, or rfc2822, rfc3339, or epoch.
+            ISO-8601 dates and times are always recognized
+    -t, --type <db_type>
+            The type of database to create, valid types are sqlite, postgres, and mysql
+
+        --delimiter <delimiter>
+            The field delimiter, use 'tab' for tab delimited files. A comma when not given
+
+    -d, --directories <directories>...
+            The directories that contain CSV files to be processed, a comma delimited string of paths
+
+        --encoding <encoding>
+            The character encoding of the CSV file(s), e.g. utf-8, latin1, or utf-16le, detected for each file when not
+            given
+        --escape <escape>                         The character used to escape quotes inside of quoted fields
+    -e, --extension <extension>
+            the file extension for the CSV files to be parsed, can be a comma delimited list of extensions [default:
+            csv]
+    -f, --files <files>...
+            The CSV files to be processed, can be /path/to/files/ or a comma delimited string of paths, use - to read
+            from stdin
+        --header-case <header_case>
+            The case of the column names made from the headers, valid cases are keep, snake, and camel, keep when not
+            given. Accented letters lose their accents, other characters that can't be in a name become underscores and
+            repeated names are numbered
+        --header-files <header_files>...
+            Files that have a header row, their first row isn't checked, a comma delimited list of file names or paths
+
+        --header-row <header_row>
+            The row the header is on, counted from 1 after the lines skipped with --skip-lines, the rows above it are
+            skipped. The first row of data when there are no headers
+        --locale <locale>
+            The locale numbers are written in, e.g. en_US for 1,234.56 or de_DE for 1.234,56. Without it numbers have no
+            thousands separators and a . before the decimals
+        --max-errors <max_errors>
+            The most records of a transaction that can fail to be stored, the transaction is rolled back when more of
+            its records fail so a rerun starts clean. Without it a transaction is only rolled back when loading stops
+            with an error
+    -n, --name <name>
+            Name of the database to be created, also the table name for data read from stdin
+
+        --no-header-files <no_header_files>...
+            Files that have no header row, their first row isn't checked, a comma delimited list of file names or paths
+
+        --null-values <null_values>...
+            The values that are stored as NULL, a comma delimited list. NA, NULL, \\N, and - when not given
+
+        --numeric-columns <numeric_columns>...
+            Columns to store as numbers even when their values look like identifiers, values with leading zeros or, with
+            --fixed-width-ids, that are all the same number of digits are stored as strings otherwise, a comma delimited
+            list of column names
+    -o, --one-table <one_table>                   Store the parsed data in a single table with the name given here
+        --primary-key <primary_key>...
+            The columns of each table's primary key, a comma delimited list of column names
+
+        --quote <quote>                           The character used to quote fields. A double quote when not given
+        --ragged-rows <ragged_rows>
+            What is done with rows that have more or fewer fields than the header, valid options are strict, pad,
+            truncate, and side-file, strict when not given. strict skips them, pad stores missing fields as NULL,
+            truncate also drops extra fields and side-file writes them to a .bad.csv file next to the input
+        --skip-footer <skip_footer>
+            The number of rows at the end of each file that are dropped, like totals or a generated on line
+
+        --skip-lines <skip_lines>
+            The number of lines at the beginning of each file that are skipped before the header, like titles or notes,
+            the lines don't have to be CSV
+        --skip-pattern <skip_pattern>
+            Rows that match this regular expression are dropped, the row's fields are matched as they are in the file
+            joined by the delimiter, e.g. ^Total,
+        --timezone <timezone>
+            The timezone of dates and times that don't have one, e.g. America/New_York or UTC. Without it they are
+            stored without a timezone
+        --transaction <transaction_mode>
+            How the tables and records are committed, valid modes are file, run, or a number of records, file when not
+            given. file commits each file's table and records together, run commits every file together at the end and a
+            number commits each file's records every that many records
+        --trim <trim>
+            Whitespace to trim, valid options are none, headers, fields, and all, none when not given
+
 ", env!("CARGO_PKG_VERSION"));;
 
 
@@ -156,7 +356,7 @@ fn calling_csvto_with_db_with_version() {
 
 #[test]
 fn calling_csvto_with_db_without_files_or_directories_and_no_piped_input() {
-    let db_err_msg = "error: either -f, --files or -d, --directories must be provided\n";
+    let db_err_msg = "error: either -f, --files or -d, --directories must be provided, or CSV data piped in\n";
 
     let output = Command::new(CMD_PATH)
         .arg("db")